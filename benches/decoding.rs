@@ -0,0 +1,44 @@
+//! Decoding into a shared [`Arena`] against a `String` per decoded segment.
+//! Every key and value in these inputs needs decoding.
+
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use querystrong::{Arena, QueryStrong};
+use std::hint::black_box;
+
+const SIZES: [usize; 3] = [100, 1_000, 10_000];
+
+fn encoded_form(n: usize) -> String {
+    (0..n)
+        .map(|i| format!("field+{i}=some+value+%C3%A9+{i}"))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+fn decoding(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decoding");
+    for n in SIZES {
+        group.throughput(Throughput::Elements(n as u64));
+        let input = encoded_form(n);
+
+        group.bench_with_input(BenchmarkId::new("per_segment", n), &input, |b, input| {
+            b.iter(|| QueryStrong::parse(black_box(input)))
+        });
+
+        group.bench_with_input(BenchmarkId::new("arena", n), &input, |b, input| {
+            b.iter(|| {
+                let mut arena = Arena::new();
+                QueryStrong::parse_in(black_box(input), &mut arena).len();
+                arena
+            })
+        });
+
+        let mut arena = Arena::new();
+        group.bench_with_input(BenchmarkId::new("arena_reused", n), &input, |b, input| {
+            b.iter(|| QueryStrong::parse_in(black_box(input), &mut arena).len())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, decoding);
+criterion_main!(benches);
@@ -0,0 +1,65 @@
+//! Indexed lists should parse and drain in linear time: the time per element
+//! reported for each size should stay flat as the size grows.
+
+use criterion::{BatchSize, BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use querystrong::QueryStrong;
+use std::hint::black_box;
+
+const SIZES: [usize; 4] = [1_000, 4_000, 16_000, 64_000];
+
+fn indexed(n: usize) -> String {
+    (0..n)
+        .map(|i| format!("a[{i}]={i}"))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+fn indexed_rows(n: usize) -> String {
+    (0..n)
+        .map(|i| format!("rows[{i}][id]={i}&rows[{i}][name]=row"))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+fn parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    for n in SIZES {
+        group.throughput(Throughput::Elements(n as u64));
+
+        let input = indexed(n);
+        group.bench_with_input(BenchmarkId::new("indexed", n), &input, |b, input| {
+            b.iter(|| QueryStrong::parse(black_box(input)))
+        });
+
+        let input = indexed_rows(n);
+        group.bench_with_input(BenchmarkId::new("indexed_rows", n), &input, |b, input| {
+            b.iter(|| QueryStrong::parse(black_box(input)))
+        });
+    }
+    group.finish();
+}
+
+fn take(c: &mut Criterion) {
+    let mut group = c.benchmark_group("take");
+    for n in SIZES {
+        group.throughput(Throughput::Elements(n as u64));
+        let input = indexed(n);
+        let qs = QueryStrong::parse(&input);
+        group.bench_with_input(BenchmarkId::new("from_end", n), &qs, |b, qs| {
+            b.iter_batched(
+                || qs.clone(),
+                |mut qs| {
+                    for i in (0..n).rev() {
+                        qs.take(format!("a[{i}]").as_str());
+                    }
+                    qs
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, parse, take);
+criterion_main!(benches);
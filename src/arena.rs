@@ -0,0 +1,104 @@
+use crate::{IndexPath, percent_coding};
+use alloc::{borrow::Cow, string::String, vec::Vec};
+use core::mem;
+
+/// Reusable storage for the decoded keys and values of
+/// [`QueryStrong::parse_in`](crate::QueryStrong::parse_in).
+///
+/// [`QueryStrong::parse`](crate::QueryStrong::parse) gives every segment that
+/// needs percent-decoding its own `String`.  `parse_in` decodes all of them
+/// back to back into this one buffer instead, and the parsed
+/// [`Value`](crate::Value)s borrow their slices of it.  A parse therefore
+/// costs a constant number of allocations for string data, and usually none
+/// once the arena is reused for inputs no larger than earlier ones.
+///
+/// ```
+/// use querystrong::{Arena, QueryStrong};
+///
+/// let mut arena = Arena::new();
+/// let qs = QueryStrong::parse_in("name=caf%C3%A9&q=a+b&page=2", &mut arena);
+/// assert_eq!(qs.get_str("name"), Some("café"));
+/// assert_eq!(qs.get_str("q"), Some("a b"));
+/// assert_eq!(qs, QueryStrong::parse("name=caf%C3%A9&q=a+b&page=2"));
+/// drop(qs);
+///
+/// // Only the segments that needed decoding are stored
+/// assert_eq!(arena.as_str(), "caféa b");
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Arena {
+    buf: String,
+    // For each decoded segment, its offset in the input and its end in `buf`
+    segments: Vec<(usize, usize)>,
+}
+
+impl Arena {
+    /// An empty arena.  It allocates on first use.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The decoded segments of the most recent parse, concatenated in input
+    /// order.
+    pub fn as_str(&self) -> &str {
+        &self.buf
+    }
+
+    /// Decode every segment of `s` that `parse_in` will ask for, in the order
+    /// it will ask for them.
+    pub(crate) fn fill(&mut self, s: &str) {
+        let mut bytes = mem::take(&mut self.buf).into_bytes();
+        bytes.clear();
+        self.segments.clear();
+        if percent_coding::needs_decoding(s) {
+            // decoding never lengthens a segment
+            bytes.reserve(s.len());
+        }
+
+        let segments = &mut self.segments;
+        let mut push = |raw: &str| {
+            if !percent_coding::needs_decoding(raw) {
+                return;
+            }
+            let offset = offset_in(s, raw);
+            let start = bytes.len();
+            percent_coding::decode_into(raw, &mut bytes);
+            if core::str::from_utf8(&bytes[start..]).is_err() {
+                let repaired = String::from_utf8_lossy(&bytes[start..]).into_owned();
+                bytes.truncate(start);
+                bytes.extend_from_slice(repaired.as_bytes());
+            }
+            segments.push((offset, bytes.len()));
+        };
+
+        // The same key step as the parse itself, so that a rejected key's
+        // value is not stored
+        let mut path = IndexPath::default();
+        for (k, v) in crate::split_pairs(s) {
+            let key = path.parse_key(k, |raw| {
+                push(raw);
+                Cow::Borrowed(raw)
+            });
+            if let (Ok(()), Some(v)) = (key, v) {
+                push(v);
+            }
+        }
+
+        self.buf = String::from_utf8(bytes).expect("each segment is valid UTF-8");
+    }
+
+    /// The decoded segments with their offsets in the input, in the order
+    /// [`fill`](Arena::fill) stored them.
+    pub(crate) fn segments(&self) -> impl Iterator<Item = (usize, &str)> {
+        self.segments.iter().scan(0, |start, &(offset, end)| {
+            let segment = &self.buf[*start..end];
+            *start = end;
+            Some((offset, segment))
+        })
+    }
+}
+
+/// The byte offset of `segment`, a slice of `s`, within `s`.
+pub(crate) fn offset_in(s: &str, segment: &str) -> usize {
+    segment.as_ptr() as usize - s.as_ptr() as usize
+}
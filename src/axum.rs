@@ -0,0 +1,203 @@
+use crate::{ParseErrors, QueryStrong, UriExt, de};
+use axum_core::{
+    extract::{FromRequest, FromRequestParts, Request},
+    response::{IntoResponse, Response},
+};
+use http::{Method, StatusCode, header, request::Parts};
+use serde::de::DeserializeOwned;
+use std::{
+    convert::Infallible,
+    ops::{Deref, DerefMut},
+    string::{String, ToString},
+    vec::Vec,
+};
+use thiserror::Error;
+
+/// Extracts the request query string, keeping any parse errors available
+/// through [`QueryStrong::errors`].  Never rejects.  Requires the `axum`
+/// feature.
+impl<S: Send + Sync> FromRequestParts<S> for QueryStrong<'static> {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(parts.uri.query_strong().into_owned())
+    }
+}
+
+/// An axum extractor that deserializes the query string into `T`, with
+/// support for nested brackets (`filter[tags][]=a`).  Requires the `axum`
+/// feature.
+///
+/// Leaves are parsed into the requested type, so `page=2` fills a `u32`
+/// field, and a single `tags=a` fills a `Vec<String>`.  Any parse error,
+/// or a query that does not fit `T`, is rejected with a
+/// [`QueryStrongRejection`].
+///
+/// ```
+/// use querystrong::StrongQuery;
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Filter {
+///     status: Option<String>,
+///     tags: Vec<String>,
+/// }
+///
+/// #[derive(Deserialize)]
+/// struct Search {
+///     page: u32,
+///     filter: Filter,
+/// }
+///
+/// async fn search(StrongQuery(search): StrongQuery<Search>) -> String {
+///     format!("page {} tagged {:?}", search.page, search.filter.tags)
+/// }
+/// # let _: axum::Router = axum::Router::new().route("/", axum::routing::get(search));
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StrongQuery<T>(pub T);
+
+/// An axum extractor that deserializes an `application/x-www-form-urlencoded`
+/// body into `T` in the same way as [`StrongQuery`].  For `GET` and `HEAD`
+/// requests it reads the query string instead.  Requires the `axum` feature.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StrongForm<T>(pub T);
+
+/// Why [`StrongQuery`] or [`StrongForm`] rejected a request.
+///
+/// Responds with `400 Bad Request`, or `415 Unsupported Media Type` for a
+/// form body of another type, and a JSON body listing each error:
+///
+/// ```json
+/// {"errors": ["could not append (`\"1\"`, `Some(String(\"b\"))`, `\"2\"`)"]}
+/// ```
+#[derive(Debug, Error, Clone, PartialEq, Eq)]
+pub enum QueryStrongRejection {
+    /// The query string or body could not be parsed.
+    #[error("{0}")]
+    Parse(ParseErrors<'static>),
+
+    /// The parameters parsed, but do not fit the target type.
+    #[error("failed to deserialize parameters: {0}")]
+    Deserialize(String),
+
+    /// A [`StrongForm`] body was not `application/x-www-form-urlencoded`.
+    #[error("expected `application/x-www-form-urlencoded` content")]
+    UnsupportedMediaType,
+
+    /// A [`StrongForm`] body could not be read as UTF-8.
+    #[error("failed to read body: {0}")]
+    Body(String),
+}
+
+impl QueryStrongRejection {
+    /// The status code of the response.
+    pub fn status(&self) -> StatusCode {
+        match self {
+            QueryStrongRejection::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+
+    /// The messages listed in the response body, one for each parse error.
+    pub fn messages(&self) -> Vec<String> {
+        match self {
+            QueryStrongRejection::Parse(errors) => {
+                errors.errors().iter().map(ToString::to_string).collect()
+            }
+            other => vec![other.to_string()],
+        }
+    }
+}
+
+impl IntoResponse for QueryStrongRejection {
+    fn into_response(self) -> Response {
+        let body = serde_json::json!({ "errors": self.messages() });
+        (
+            self.status(),
+            [(header::CONTENT_TYPE, "application/json")],
+            body.to_string(),
+        )
+            .into_response()
+    }
+}
+
+fn extract<T: DeserializeOwned>(input: &str) -> Result<T, QueryStrongRejection> {
+    let querystrong = QueryStrong::parse_strict(input)
+        .map_err(|errors| QueryStrongRejection::Parse(errors.into_owned()))?;
+    de::from_value(&querystrong)
+        .map_err(|error| QueryStrongRejection::Deserialize(error.to_string()))
+}
+
+impl<T, S> FromRequestParts<S> for StrongQuery<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = QueryStrongRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        extract(parts.uri.query().unwrap_or_default()).map(StrongQuery)
+    }
+}
+
+impl<T, S> FromRequest<S> for StrongForm<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = QueryStrongRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        if req.method() == Method::GET || req.method() == Method::HEAD {
+            return extract(req.uri().query().unwrap_or_default()).map(StrongForm);
+        }
+
+        let is_form = req
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .is_some_and(|content_type| {
+                content_type.split(';').next().is_some_and(|mime| {
+                    mime.trim()
+                        .eq_ignore_ascii_case("application/x-www-form-urlencoded")
+                })
+            });
+        if !is_form {
+            return Err(QueryStrongRejection::UnsupportedMediaType);
+        }
+
+        let body = String::from_request(req, state)
+            .await
+            .map_err(|rejection| QueryStrongRejection::Body(rejection.body_text()))?;
+        extract(&body).map(StrongForm)
+    }
+}
+
+impl<T> Deref for StrongQuery<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for StrongQuery<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T> Deref for StrongForm<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for StrongForm<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
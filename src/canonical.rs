@@ -0,0 +1,183 @@
+use crate::{IndexPath, Indexer, QueryStrong, Value, Visit, VisitMut, walk_value_mut};
+use alloc::{
+    borrow::Cow,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{convert::TryInto, fmt::Debug};
+
+/// Options for [`QueryStrong::canonicalize_with`] and friends.
+///
+/// ```
+/// use querystrong::{CanonicalOptions, QueryStrong};
+///
+/// let options = CanonicalOptions::new()
+///     .ignore_prefix("utm_")
+///     .ignore("session[nonce]");
+/// let a = QueryStrong::parse("q=rust&utm_source=mail&session[nonce]=1&session[id]=x");
+/// let b = QueryStrong::parse("session[id]=x&q=rust&utm_campaign=spring");
+/// assert_eq!(a.canonical_string_with(&options), "q=rust&session[id]=x");
+/// assert_eq!(a.fingerprint_with(&options), b.fingerprint_with(&options));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CanonicalOptions {
+    ignore: Vec<IndexPath<'static>>,
+    ignore_prefixes: Vec<String>,
+}
+
+impl CanonicalOptions {
+    /// Options that ignore nothing; the same as the methods without `_with`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Leave out the value at `path`, and everything below it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `path` is not a valid [`IndexPath`].
+    pub fn ignore<K>(mut self, path: K) -> Self
+    where
+        K: TryInto<IndexPath<'static>>,
+        K::Error: Debug,
+    {
+        let path = path.try_into().expect("invalid ignored path");
+        self.ignore.push(path);
+        self
+    }
+
+    /// Leave out every top-level key that starts with `prefix`, such as
+    /// `utm_` for tracking parameters.
+    pub fn ignore_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.ignore_prefixes.push(prefix.into());
+        self
+    }
+}
+
+impl<'a> QueryStrong<'a> {
+    /// A copy of this query in canonical form.
+    ///
+    /// Two query strings that differ only in key order, percent-encoding
+    /// (`%41` and `A`), repeated-key versus `[]` list syntax, or a bare key
+    /// versus an empty value (`k` and `k=`) canonicalize to equal values.
+    /// Bare keys become empty strings, and containers left empty are removed.
+    /// Parse errors are not carried over.
+    pub fn canonicalize(&self) -> QueryStrong<'a> {
+        self.canonicalize_with(&CanonicalOptions::default())
+    }
+
+    /// Like [`canonicalize`](QueryStrong::canonicalize), leaving out what
+    /// `options` ignores.
+    pub fn canonicalize_with(&self, options: &CanonicalOptions) -> QueryStrong<'a> {
+        let mut value = self.value.clone();
+        if let Value::Map(m) = &mut value {
+            m.retain(|key, _| {
+                !options
+                    .ignore_prefixes
+                    .iter()
+                    .any(|prefix| key.starts_with(&**prefix))
+            });
+        }
+
+        for path in &options.ignore {
+            value.take(path.clone());
+        }
+
+        value.visit_mut(&mut EmptyToString);
+        value.compact();
+        QueryStrong::from(value)
+    }
+
+    /// The canonical form serialized as a query string, suitable as a cache
+    /// key.
+    ///
+    /// The same as `self.canonicalize().to_string()`, except that elements of
+    /// a list that are themselves maps or lists are written with explicit
+    /// indices (`rows[0][id]=1&rows[0][name]=a`), which keeps them distinct
+    /// from a single list of all their entries.
+    ///
+    /// ```
+    /// use querystrong::QueryStrong;
+    /// let a = QueryStrong::parse("b=2&a=%41&tags=x&tags=y&flag");
+    /// let b = QueryStrong::parse("flag=&tags[]=x&tags[]=y&a=A&b=2");
+    /// assert_eq!(a.canonical_string(), "a=A&b=2&flag=&tags[]=x&tags[]=y");
+    /// assert_eq!(a.canonical_string(), b.canonical_string());
+    /// ```
+    pub fn canonical_string(&self) -> String {
+        self.canonical_string_with(&CanonicalOptions::default())
+    }
+
+    /// Like [`canonical_string`](QueryStrong::canonical_string), leaving out
+    /// what `options` ignores.
+    pub fn canonical_string_with(&self, options: &CanonicalOptions) -> String {
+        let mut render = Render(String::new());
+        self.canonicalize_with(options).visit(&mut render);
+        render.0
+    }
+
+    /// A 64-bit fingerprint of [`canonical_string`](QueryStrong::canonical_string).
+    ///
+    /// This is the 64-bit FNV-1a hash of the canonical string's bytes.  Unlike
+    /// [`Hash`](core::hash::Hash), it is fixed by this documentation, and so is
+    /// the same across processes, platforms and releases of this crate.
+    pub fn fingerprint(&self) -> u64 {
+        self.fingerprint_with(&CanonicalOptions::default())
+    }
+
+    /// Like [`fingerprint`](QueryStrong::fingerprint), leaving out what
+    /// `options` ignores.
+    pub fn fingerprint_with(&self, options: &CanonicalOptions) -> u64 {
+        fnv1a(self.canonical_string_with(options).as_bytes())
+    }
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+struct Render(String);
+
+impl Render {
+    fn pair(&mut self, path: &IndexPath<'_>, value: &str) {
+        if !self.0.is_empty() {
+            self.0.push('&');
+        }
+        self.0.push_str(&path.to_string());
+        self.0.push('=');
+        self.0.push_str(&crate::encode(value));
+    }
+}
+
+impl<'a> Visit<'a> for Render {
+    fn visit_list(&mut self, path: &mut IndexPath<'a>, list: &[Value<'a>]) {
+        for (n, value) in list.iter().enumerate() {
+            path.push_back(match value {
+                Value::String(_) | Value::Encoded(_) | Value::Empty => Indexer::Empty,
+                _ => Indexer::Number(n),
+            });
+            self.visit_value(path, value);
+            path.pop_back();
+        }
+    }
+
+    fn visit_string(&mut self, path: &mut IndexPath<'a>, string: &str) {
+        self.pair(path, string);
+    }
+
+    fn visit_empty(&mut self, path: &mut IndexPath<'a>) {
+        self.pair(path, "");
+    }
+}
+
+struct EmptyToString;
+
+impl<'a> VisitMut<'a> for EmptyToString {
+    fn visit_value_mut(&mut self, path: &mut IndexPath<'a>, value: &mut Value<'a>) {
+        match value {
+            Value::Empty => *value = Value::String(Cow::Borrowed("")),
+            _ => walk_value_mut(self, path, value),
+        }
+    }
+}
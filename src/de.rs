@@ -0,0 +1,273 @@
+//! A `serde` deserializer over a parsed [`Value`] tree, for the typed
+//! extractors.
+//!
+//! Everything in a query string is a string, so leaves are parsed into the
+//! type that is asked for (`"2"` into `u32`, `"true"` into `bool`).  Maps
+//! deserialize as maps and structs, lists and sparse lists as sequences in
+//! index order, and a single string as a one-element sequence.  A bare key or
+//! an empty string is `None` for an `Option`.
+
+use crate::Value;
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, IntoDeserializer, MapAccess, SeqAccess, Unexpected,
+    Visitor,
+};
+use std::{
+    borrow::Cow,
+    fmt::{self, Display, Formatter},
+    string::{String, ToString},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct DeError(String);
+
+impl Display for DeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for DeError {}
+
+impl de::Error for DeError {
+    fn custom<T: Display>(msg: T) -> Self {
+        DeError(msg.to_string())
+    }
+}
+
+pub(crate) fn from_value<T: DeserializeOwned>(value: &Value<'_>) -> Result<T, DeError> {
+    T::deserialize(ValueDeserializer(value))
+}
+
+struct ValueDeserializer<'v, 'a>(&'v Value<'a>);
+
+struct Leaf<'s>(Cow<'s, str>);
+
+impl ValueDeserializer<'_, '_> {
+    fn leaf<'de, V: Visitor<'de>>(&self, visitor: &V) -> Result<Leaf<'_>, DeError> {
+        match self.0 {
+            Value::String(s) => Ok(Leaf(Cow::Borrowed(s))),
+            Value::Encoded(e) => Ok(Leaf(Cow::Borrowed(e.as_str()))),
+            Value::Empty => Ok(Leaf(Cow::Borrowed(""))),
+            Value::Map(_) => Err(de::Error::invalid_type(Unexpected::Map, visitor)),
+            Value::List(_) | Value::SparseList(_) => {
+                Err(de::Error::invalid_type(Unexpected::Seq, visitor))
+            }
+        }
+    }
+}
+
+macro_rules! parse_leaf {
+    ($($method:ident => $visit:ident,)*) => {$(
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+            match self.0.parse() {
+                Ok(parsed) => visitor.$visit(parsed),
+                Err(_) => Err(de::Error::invalid_value(Unexpected::Str(&self.0), &visitor)),
+            }
+        }
+    )*};
+}
+
+macro_rules! forward_to_leaf {
+    ($($method:ident,)*) => {$(
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+            self.leaf(&visitor)?.$method(visitor)
+        }
+    )*};
+}
+
+impl<'de> de::Deserializer<'de> for Leaf<'_> {
+    type Error = DeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        visitor.visit_str(&self.0)
+    }
+
+    parse_leaf! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+        deserialize_char => visit_char,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        if self.0.is_empty() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        if self.0.is_empty() {
+            visitor.visit_unit()
+        } else {
+            Err(de::Error::invalid_value(Unexpected::Str(&self.0), &visitor))
+        }
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        visitor.visit_seq(Seq(std::iter::once(self)))
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        visitor.visit_enum(self.0.as_ref().into_deserializer())
+    }
+
+    serde::forward_to_deserialize_any! {
+        str string bytes byte_buf unit_struct tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+impl<'de> de::Deserializer<'de> for ValueDeserializer<'_, '_> {
+    type Error = DeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        match self.0 {
+            Value::Map(m) => visitor.visit_map(Map {
+                entries: m.iter(),
+                value: None,
+            }),
+            Value::List(l) => visitor.visit_seq(Seq(l.iter().map(ValueDeserializer))),
+            Value::SparseList(m) => visitor.visit_seq(Seq(m.values().map(ValueDeserializer))),
+            Value::String(s) => visitor.visit_str(s),
+            Value::Encoded(e) => visitor.visit_str(e.as_str()),
+            Value::Empty => visitor.visit_unit(),
+        }
+    }
+
+    forward_to_leaf! {
+        deserialize_bool,
+        deserialize_i8,
+        deserialize_i16,
+        deserialize_i32,
+        deserialize_i64,
+        deserialize_i128,
+        deserialize_u8,
+        deserialize_u16,
+        deserialize_u32,
+        deserialize_u64,
+        deserialize_u128,
+        deserialize_f32,
+        deserialize_f64,
+        deserialize_char,
+        deserialize_str,
+        deserialize_string,
+        deserialize_unit,
+        deserialize_identifier,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        match self.0 {
+            Value::Empty => visitor.visit_none(),
+            Value::String(s) if s.is_empty() => visitor.visit_none(),
+            Value::Encoded(e) if e.is_empty() => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        match self.0 {
+            Value::String(_) | Value::Encoded(_) | Value::Empty => {
+                visitor.visit_seq(Seq(std::iter::once(ValueDeserializer(self.0))))
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, DeError> {
+        self.leaf(&visitor)?
+            .deserialize_enum(name, variants, visitor)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bytes byte_buf unit_struct tuple tuple_struct map struct ignored_any
+    }
+}
+
+struct Seq<I>(I);
+
+impl<'de, I, D> SeqAccess<'de> for Seq<I>
+where
+    I: Iterator<Item = D>,
+    D: de::Deserializer<'de, Error = DeError>,
+{
+    type Error = DeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, DeError> {
+        self.0.next().map(|d| seed.deserialize(d)).transpose()
+    }
+}
+
+struct Map<'v, 'a, I> {
+    entries: I,
+    value: Option<&'v Value<'a>>,
+}
+
+impl<'de, 'v, 'a: 'v, I> MapAccess<'de> for Map<'v, 'a, I>
+where
+    I: Iterator<Item = (&'v Cow<'a, str>, &'v Value<'a>)>,
+{
+    type Error = DeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, DeError> {
+        match self.entries.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(Leaf(Cow::Borrowed(key))).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, DeError> {
+        let value = self
+            .value
+            .take()
+            .ok_or_else(|| de::Error::custom("value requested before key"))?;
+        seed.deserialize(ValueDeserializer(value))
+    }
+}
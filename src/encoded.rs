@@ -0,0 +1,92 @@
+use crate::{decode, percent_coding};
+use alloc::{borrow::Cow, boxed::Box, string::String};
+use core::fmt::{self, Debug, Formatter};
+use once_cell::race::OnceBox;
+
+/// A string value whose percent-decoding is deferred until it is first read,
+/// as held by [`Value::Encoded`](crate::Value::Encoded).
+///
+/// The raw slice of the input is kept, and the decoded string is computed
+/// and cached the first time [`as_str`](EncodedStr::as_str) is called, so
+/// each value is decoded at most once however often it is read.  The cache
+/// is safe to fill from several threads; `EncodedStr` is `Send` and `Sync`.
+///
+/// ```
+/// use querystrong::EncodedStr;
+///
+/// let encoded = EncodedStr::new("caf%C3%A9+au+lait");
+/// assert_eq!(encoded.raw(), "caf%C3%A9+au+lait");
+/// assert_eq!(encoded.as_str(), "café au lait");
+/// ```
+#[derive(Clone)]
+pub struct EncodedStr<'a> {
+    raw: &'a str,
+    decoded: OnceBox<String>,
+}
+
+impl<'a> EncodedStr<'a> {
+    /// Wrap the still-encoded `raw`, doing no decoding yet.
+    pub fn new(raw: &'a str) -> Self {
+        Self {
+            raw,
+            decoded: OnceBox::new(),
+        }
+    }
+
+    /// The encoded slice of the input.
+    pub fn raw(&self) -> &'a str {
+        self.raw
+    }
+
+    /// The decoded string, decoding and caching it on the first call.
+    pub fn as_str(&self) -> &str {
+        if !percent_coding::needs_decoding(self.raw) {
+            return self.raw;
+        }
+        self.decoded
+            .get_or_init(|| Box::new(decode(self.raw).into_owned()))
+    }
+
+    /// Whether the decoded string is empty.  Does not decode.
+    pub fn is_empty(&self) -> bool {
+        self.raw.is_empty()
+    }
+
+    /// The length of the decoded string in bytes, counted without allocating
+    /// if it has not been decoded yet.
+    pub fn len(&self) -> usize {
+        match self.decoded.get() {
+            Some(decoded) => decoded.len(),
+            None => percent_coding::decoded_len(self.raw),
+        }
+    }
+
+    /// The decoded string, reusing the cached one if there is one.
+    pub fn into_cow(self) -> Cow<'a, str> {
+        match self.decoded.get() {
+            Some(decoded) => Cow::Owned(decoded.clone()),
+            None => decode(self.raw),
+        }
+    }
+}
+
+impl<'a> From<&'a str> for EncodedStr<'a> {
+    fn from(raw: &'a str) -> Self {
+        Self::new(raw)
+    }
+}
+
+impl Debug for EncodedStr<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("EncodedStr").field(&self.raw).finish()
+    }
+}
+
+// Compares the decoded strings, as `Value` compares an `Encoded` leaf.
+impl PartialEq for EncodedStr<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.raw == other.raw || self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for EncodedStr<'_> {}
@@ -1,10 +1,10 @@
-use std::{
-    borrow::Cow,
+use alloc::{borrow::Cow, vec::Vec};
+use core::{
     convert::Infallible,
     fmt::{self, Display, Formatter},
 };
 
-use crate::{Indexer, Value, index_path::IndexPathState};
+use crate::{IndexPath, Indexer, Value, index_path::IndexPathState};
 use thiserror::Error;
 
 /// An error produced during query-string parsing or value mutation.
@@ -29,12 +29,35 @@ pub enum Error<'a> {
     #[error("could not append (`{0:?}`, `{1:?}`, `{2:?}`)")]
     CouldNotAppend(Value<'a>, Option<Indexer<'a>>, Value<'a>),
 
+    /// A key path was nested deeper than [`Value::MAX_DEPTH`](crate::Value::MAX_DEPTH).
+    ///
+    /// The field is the depth of the rejected path.  Parsing a query string
+    /// stops counting one level past the limit, so there it is always
+    /// `MAX_DEPTH + 1`.
+    #[error("key is nested at least {0} levels deep, more than the limit of {max}", max = Value::MAX_DEPTH)]
+    TooDeep(usize),
+
     /// The index-path parser encountered an unexpected bracket character.
     ///
     /// The fields are: the unexpected character (if any), the parser state at
     /// the time, and the full key string being parsed.
     #[error("parsing indexer ran into `{0:?}` in state `{1:?}` when parsing {2:?}")]
     CouldNotParseIndexer(Option<char>, IndexPathState, Cow<'a, str>),
+
+    /// A path given in [`PathNotation::JsonPointer`](crate::PathNotation::JsonPointer)
+    /// did not start with `/` or contained a `~` not followed by `0` or `1`.
+    #[error("could not parse {0:?} as a JSON pointer")]
+    InvalidJsonPointer(Cow<'a, str>),
+
+    /// [`Value::require`](crate::Value::require) found nothing, or only an
+    /// empty value, at the given path.
+    #[error("missing required parameter `{0}`")]
+    MissingParameter(IndexPath<'a>),
+
+    /// [`Value::permit_strict`](crate::Value::permit_strict) found paths that
+    /// none of its filters allow.
+    #[error("unpermitted parameters: {}", .0.iter().map(|path| format!("`{path}`")).collect::<Vec<_>>().join(", "))]
+    UnpermittedParameters(Vec<IndexPath<'a>>),
 }
 
 impl<'a> Error<'a> {
@@ -49,6 +72,14 @@ impl<'a> Error<'a> {
             Error::CouldNotParseIndexer(a, b, c) => {
                 Error::CouldNotParseIndexer(a, b, Cow::Owned(c.into_owned()))
             }
+            Error::TooDeep(depth) => Error::TooDeep(depth),
+            Error::InvalidJsonPointer(path) => {
+                Error::InvalidJsonPointer(Cow::Owned(path.into_owned()))
+            }
+            Error::MissingParameter(path) => Error::MissingParameter(path.into_owned()),
+            Error::UnpermittedParameters(paths) => {
+                Error::UnpermittedParameters(paths.into_iter().map(IndexPath::into_owned).collect())
+            }
         }
     }
 }
@@ -59,9 +90,9 @@ impl From<Infallible> for Error<'_> {
     }
 }
 
-pub type Result<'a, T> = std::result::Result<T, Error<'a>>;
+pub type Result<'a, T> = core::result::Result<T, Error<'a>>;
 
-pub type ParseResult<'a, T> = std::result::Result<T, ParseErrors<'a>>;
+pub type ParseResult<'a, T> = core::result::Result<T, ParseErrors<'a>>;
 
 /// A collection of [`Error`]s accumulated while parsing a query string.
 ///
@@ -74,7 +105,7 @@ pub struct ParseErrors<'a> {
     input: Cow<'a, str>,
     errors: Vec<Error<'a>>,
 }
-impl std::error::Error for ParseErrors<'_> {}
+impl core::error::Error for ParseErrors<'_> {}
 
 impl Display for ParseErrors<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
@@ -108,6 +139,17 @@ impl<'a> ParseErrors<'a> {
         self.errors.push(e)
     }
 
+    // Empty, for parsing `input`, keeping the allocation for the error list.
+    pub(crate) fn recycle<'b>(mut self, input: &'b str) -> ParseErrors<'b> {
+        self.errors.clear();
+        ParseErrors {
+            input: input.into(),
+            // The list is empty, so this copies nothing, and the collect
+            // reuses its buffer in place
+            errors: self.errors.into_iter().map(Error::into_owned).collect(),
+        }
+    }
+
     pub fn into_owned(self) -> ParseErrors<'static> {
         ParseErrors {
             input: Cow::Owned(self.input.into_owned()),
@@ -124,4 +166,19 @@ impl<'a> ParseErrors<'a> {
     pub fn errors(&self) -> &[Error<'a>] {
         &self.errors
     }
+
+    /// The number of errors.
+    pub fn len(&self) -> usize {
+        self.errors.len()
+    }
+
+    /// Whether there are no errors, as after [`clear`](ParseErrors::clear).
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Remove all errors, keeping the allocated capacity.
+    pub fn clear(&mut self) {
+        self.errors.clear();
+    }
 }
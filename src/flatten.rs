@@ -0,0 +1,301 @@
+use crate::{Error, IndexPath, Indexer, Result, Value, Visit};
+use alloc::{
+    borrow::{Cow, ToOwned},
+    string::String,
+    vec::Vec,
+};
+use core::fmt::Write;
+
+/// The textual form used for full paths by [`Value::flatten_with`] and
+/// [`Value::unflatten_with`].
+///
+/// Each notation escapes exactly the characters it needs so that any path
+/// produced by [`render`](PathNotation::render) parses back to the same
+/// [`IndexPath`]:
+///
+/// | Notation      | Example                | Escaping                                        |
+/// |---------------|------------------------|-------------------------------------------------|
+/// | `Brackets`    | `user[tags][]`         | `%`, `+`, `[`, `]` and all-digit keys as `%XX`  |
+/// | `Dotted`      | `user.tags.`           | `%`, `+`, `.` and all-digit keys as `%XX`       |
+/// | `JsonPointer` | `/user/tags/-`         | `~` as `~0`, `/` as `~1` (RFC 6901)             |
+///
+/// In every notation an empty-bracket append segment (`[]`) is rendered as an
+/// empty segment, or as `-` in JSON Pointer, and a numeric index as its
+/// decimal digits.  JSON Pointer has no escape for map keys that look like
+/// list indices, so a map key of `-` or of only ASCII digits will come back
+/// as a list segment.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PathNotation {
+    /// Bracket notation as used in query strings, without percent-encoding
+    /// anything that does not need it: `user[name][first]`.
+    #[default]
+    Brackets,
+    /// Dot-separated segments: `user.name.first`.
+    Dotted,
+    /// [RFC 6901](https://www.rfc-editor.org/rfc/rfc6901) JSON Pointer:
+    /// `/user/name/first`.
+    JsonPointer,
+}
+
+impl PathNotation {
+    /// Render `path` in this notation.
+    pub fn render(self, path: &IndexPath<'_>) -> String {
+        let mut out = String::new();
+        for (i, indexer) in path.iter().enumerate() {
+            match self {
+                PathNotation::Brackets if i == 0 => {}
+                PathNotation::Brackets => out.push('['),
+                PathNotation::Dotted if i == 0 => {}
+                PathNotation::Dotted => out.push('.'),
+                PathNotation::JsonPointer => out.push('/'),
+            }
+
+            match (self, indexer) {
+                (PathNotation::JsonPointer, Indexer::Empty) => out.push('-'),
+                (_, Indexer::Empty) => {}
+                (_, Indexer::Number(n)) => {
+                    let _ = write!(out, "{n}");
+                }
+                (_, Indexer::String(s)) => self.escape_key(s, &mut out),
+            }
+
+            if self == PathNotation::Brackets && i > 0 {
+                out.push(']');
+            }
+        }
+        out
+    }
+
+    /// Parse a path previously produced by [`render`](PathNotation::render).
+    ///
+    /// Segments that need no unescaping are borrowed from `s`.
+    pub fn parse(self, s: &str) -> Result<'_, IndexPath<'_>> {
+        match self {
+            PathNotation::Brackets => IndexPath::parse(s),
+
+            PathNotation::Dotted if s.is_empty() => Ok(IndexPath::default()),
+            PathNotation::Dotted => Ok(s.split('.').map(parse_segment).collect()),
+
+            PathNotation::JsonPointer if s.is_empty() => Ok(IndexPath::default()),
+            PathNotation::JsonPointer => {
+                let Some(rest) = s.strip_prefix('/') else {
+                    return Err(Error::InvalidJsonPointer(s.into()));
+                };
+
+                rest.split('/')
+                    .map(|segment| {
+                        if segment == "-" {
+                            Ok(Indexer::Empty)
+                        } else if let Some(n) = parse_index(segment) {
+                            Ok(Indexer::Number(n))
+                        } else if segment.contains('~') {
+                            unescape_json_pointer(segment)
+                                .map(|segment| Indexer::String(Cow::Owned(segment)))
+                                .ok_or_else(|| Error::InvalidJsonPointer(s.into()))
+                        } else {
+                            Ok(Indexer::String(Cow::Borrowed(segment)))
+                        }
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    fn escape_key(self, key: &str, out: &mut String) {
+        if self == PathNotation::JsonPointer {
+            for c in key.chars() {
+                match c {
+                    '~' => out.push_str("~0"),
+                    '/' => out.push_str("~1"),
+                    c => out.push(c),
+                }
+            }
+            return;
+        }
+
+        // An all-digit key would otherwise parse back as a list index, so its
+        // first digit is escaped; parse_index rejects the `%`.
+        let all_digits = !key.is_empty() && key.bytes().all(|b| b.is_ascii_digit());
+        for (i, c) in key.char_indices() {
+            match c {
+                '%' | '+' => {
+                    let _ = write!(out, "%{:02X}", c as u8);
+                }
+                '[' | ']' if self == PathNotation::Brackets => {
+                    let _ = write!(out, "%{:02X}", c as u8);
+                }
+                '.' if self == PathNotation::Dotted => out.push_str("%2E"),
+                c if i == 0 && all_digits => {
+                    let _ = write!(out, "%{:02X}", c as u8);
+                }
+                c => out.push(c),
+            }
+        }
+    }
+}
+
+fn parse_index(segment: &str) -> Option<usize> {
+    if segment.bytes().all(|b| b.is_ascii_digit()) {
+        segment.parse().ok()
+    } else {
+        None
+    }
+}
+
+fn parse_segment(segment: &str) -> Indexer<'_> {
+    if segment.is_empty() {
+        Indexer::Empty
+    } else if let Some(n) = parse_index(segment) {
+        Indexer::Number(n)
+    } else {
+        Indexer::from(segment)
+    }
+}
+
+fn unescape_json_pointer(segment: &str) -> Option<String> {
+    let mut out = String::with_capacity(segment.len());
+    let mut chars = segment.chars();
+    while let Some(c) = chars.next() {
+        if c != '~' {
+            out.push(c);
+            continue;
+        }
+
+        match chars.next()? {
+            '0' => out.push('~'),
+            '1' => out.push('/'),
+            _ => return None,
+        }
+    }
+    Some(out)
+}
+
+struct Flatten {
+    notation: PathNotation,
+    pairs: Vec<(String, Option<String>)>,
+}
+
+impl<'a> Visit<'a> for Flatten {
+    // Leaf elements of a dense list are rendered with `[]` so that the list
+    // rebuilds as a dense list; container elements need an explicit index to
+    // keep their entries together.
+    fn visit_list(&mut self, path: &mut IndexPath<'a>, list: &[Value<'a>]) {
+        for (n, value) in list.iter().enumerate() {
+            path.push_back(match value {
+                Value::String(_) | Value::Encoded(_) | Value::Empty => Indexer::Empty,
+                _ => Indexer::Number(n),
+            });
+            self.visit_value(path, value);
+            path.pop_back();
+        }
+    }
+
+    fn visit_string(&mut self, path: &mut IndexPath<'a>, string: &str) {
+        self.pairs
+            .push((self.notation.render(path), Some(string.to_owned())));
+    }
+
+    fn visit_empty(&mut self, path: &mut IndexPath<'a>) {
+        self.pairs.push((self.notation.render(path), None));
+    }
+}
+
+impl<'a> Value<'a> {
+    /// Flatten this value into `(path, leaf)` pairs, one per
+    /// [`String`](Value::String) or [`Empty`](Value::Empty) leaf, using
+    /// [`PathNotation::Brackets`].
+    ///
+    /// Unlike [`Display`](crate::QueryStrong), neither the paths nor the
+    /// values are percent-encoded beyond what the notation requires.
+    /// [`Value::unflatten`] rebuilds the same tree from the pairs, including
+    /// the distinction between dense and sparse lists.  Containers without any
+    /// entries produce no pairs and are not rebuilt.
+    ///
+    /// ```
+    /// use querystrong::{QueryStrong, Value};
+    /// let qs = QueryStrong::parse("user[name]=jacob+r&tags[]=a&tags[]=b&ids[3]=x&flag");
+    /// let pairs = qs.flatten();
+    /// assert_eq!(
+    ///     pairs,
+    ///     [
+    ///         ("flag".into(), None),
+    ///         ("ids[3]".into(), Some("x".into())),
+    ///         ("tags[]".into(), Some("a".into())),
+    ///         ("tags[]".into(), Some("b".into())),
+    ///         ("user[name]".into(), Some("jacob r".into())),
+    ///     ]
+    /// );
+    /// assert_eq!(Value::unflatten(pairs).unwrap(), *qs);
+    /// ```
+    pub fn flatten(&self) -> Vec<(String, Option<String>)> {
+        self.flatten_with(PathNotation::Brackets)
+    }
+
+    /// Like [`flatten`](Value::flatten), rendering paths in `notation`.
+    ///
+    /// ```
+    /// use querystrong::{PathNotation, QueryStrong};
+    /// let qs = QueryStrong::parse("user[name]=jacob&user[tags][]=a");
+    /// assert_eq!(
+    ///     qs.flatten_with(PathNotation::JsonPointer),
+    ///     [
+    ///         ("/user/name".into(), Some("jacob".into())),
+    ///         ("/user/tags/-".into(), Some("a".into())),
+    ///     ]
+    /// );
+    /// ```
+    pub fn flatten_with(&self, notation: PathNotation) -> Vec<(String, Option<String>)> {
+        let mut flatten = Flatten {
+            notation,
+            pairs: vec![],
+        };
+        self.visit(&mut flatten);
+        flatten.pairs
+    }
+}
+
+impl Value<'static> {
+    /// Rebuild a value from `(path, leaf)` pairs in [`PathNotation::Brackets`],
+    /// as produced by [`flatten`](Value::flatten).
+    ///
+    /// Pairs are [`append`](Value::append)ed to an empty map in order, just as
+    /// [`QueryStrong::parse`](crate::QueryStrong::parse) appends the pairs of a
+    /// query string, so a path that repeats accumulates its leaves into a list.
+    /// A path that runs into a node of a different shape, or is deeper than
+    /// [`MAX_DEPTH`](Value::MAX_DEPTH), is an error.  With no pairs at all, the
+    /// result is an empty map.
+    ///
+    /// ```
+    /// use querystrong::{QueryStrong, Value};
+    /// let value = Value::unflatten([("a", Some("1")), ("a", Some("2"))]).unwrap();
+    /// assert_eq!(value, *QueryStrong::parse("a=1&a=2"));
+    /// ```
+    pub fn unflatten<I, K, V>(pairs: I) -> Result<'static, Self>
+    where
+        I: IntoIterator<Item = (K, Option<V>)>,
+        K: AsRef<str>,
+        V: Into<String>,
+    {
+        Self::unflatten_with(pairs, PathNotation::Brackets)
+    }
+
+    /// Like [`unflatten`](Value::unflatten), parsing paths in `notation`.
+    pub fn unflatten_with<I, K, V>(pairs: I, notation: PathNotation) -> Result<'static, Self>
+    where
+        I: IntoIterator<Item = (K, Option<V>)>,
+        K: AsRef<str>,
+        V: Into<String>,
+    {
+        let mut value = Value::new_map();
+        for (path, leaf) in pairs {
+            let path = notation
+                .parse(path.as_ref())
+                .map(IndexPath::into_owned)
+                .map_err(Error::into_owned)?;
+            let leaf = leaf.map_or(Value::Empty, |leaf| Value::String(Cow::Owned(leaf.into())));
+            value.append(path, leaf)?;
+        }
+
+        Ok(value)
+    }
+}
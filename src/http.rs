@@ -0,0 +1,130 @@
+use crate::QueryStrong;
+use http::{
+    Request, Uri,
+    uri::{Builder, PathAndQuery},
+};
+use std::string::{String, ToString};
+
+/// Extension methods for [`http::Uri`].  Requires the `http` feature.
+pub trait UriExt {
+    /// Parse the query component, borrowing from the URI.  A URI without a
+    /// query gives an empty `QueryStrong`.
+    ///
+    /// ```
+    /// use http::Uri;
+    /// use querystrong::UriExt;
+    ///
+    /// let uri: Uri = "/search?q=rust&page[size]=10".parse().unwrap();
+    /// assert_eq!(uri.query_strong()["page[size]"], "10");
+    /// ```
+    fn query_strong(&self) -> QueryStrong<'_>;
+
+    /// Replace the query component with `querystrong` serialized, or remove
+    /// it if `querystrong` is empty, keeping the scheme, authority and path.
+    ///
+    /// ```
+    /// use http::Uri;
+    /// use querystrong::UriExt;
+    ///
+    /// let mut uri: Uri = "https://example.com/search?q=rust".parse().unwrap();
+    /// let mut qs = uri.query_strong().into_owned();
+    /// qs.append("filter[tags][]", "web").unwrap();
+    /// uri.set_query_strong(&qs).unwrap();
+    /// assert_eq!(uri, "https://example.com/search?filter[tags][]=web&q=rust");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error, leaving `self` unchanged, if the URI cannot have a
+    /// path and query, such as the authority-form `example.com:443`.
+    fn set_query_strong(&mut self, querystrong: &QueryStrong<'_>) -> Result<(), http::Error>;
+}
+
+impl UriExt for Uri {
+    fn query_strong(&self) -> QueryStrong<'_> {
+        QueryStrong::parse(self.query().unwrap_or_default())
+    }
+
+    fn set_query_strong(&mut self, querystrong: &QueryStrong<'_>) -> Result<(), http::Error> {
+        let mut parts = self.clone().into_parts();
+        let path = parts
+            .path_and_query
+            .as_ref()
+            .map_or("/", PathAndQuery::path);
+        parts.path_and_query = Some(path_and_query(path, querystrong).try_into()?);
+        *self = Uri::from_parts(parts)?;
+        Ok(())
+    }
+}
+
+/// Extension methods for [`http::Request`].  Requires the `http` feature.
+pub trait RequestExt {
+    /// Parse the query component of the request URI, borrowing from it.
+    fn query_strong(&self) -> QueryStrong<'_>;
+
+    /// Replace the query component of the request URI, as
+    /// [`UriExt::set_query_strong`] does.
+    ///
+    /// ```
+    /// use http::Request;
+    /// use querystrong::RequestExt;
+    ///
+    /// let mut request = Request::get("/items?page=2&token=x").body(()).unwrap();
+    /// let mut qs = request.query_strong().into_owned();
+    /// qs.take("token");
+    /// request.set_query_strong(&qs).unwrap();
+    /// assert_eq!(request.uri(), "/items?page=2");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error, leaving the request unchanged, if the URI cannot
+    /// have a path and query.
+    fn set_query_strong(&mut self, querystrong: &QueryStrong<'_>) -> Result<(), http::Error>;
+}
+
+impl<B> RequestExt for Request<B> {
+    fn query_strong(&self) -> QueryStrong<'_> {
+        self.uri().query_strong()
+    }
+
+    fn set_query_strong(&mut self, querystrong: &QueryStrong<'_>) -> Result<(), http::Error> {
+        self.uri_mut().set_query_strong(querystrong)
+    }
+}
+
+/// Extension methods for [`http::uri::Builder`].  Requires the `http`
+/// feature.
+pub trait UriBuilderExt {
+    /// Set the path and query of the URI being built to `path` followed by
+    /// `querystrong` serialized, or to `path` alone if `querystrong` is empty.
+    ///
+    /// ```
+    /// use http::Uri;
+    /// use querystrong::{QueryStrong, UriBuilderExt};
+    ///
+    /// let qs = QueryStrong::parse("ids[]=1&ids[]=2");
+    /// let uri = Uri::builder()
+    ///     .scheme("https")
+    ///     .authority("example.com")
+    ///     .path_and_query_strong("/items", &qs)
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(uri, "https://example.com/items?ids[]=1&ids[]=2");
+    /// ```
+    fn path_and_query_strong(self, path: &str, querystrong: &QueryStrong<'_>) -> Self;
+}
+
+impl UriBuilderExt for Builder {
+    fn path_and_query_strong(self, path: &str, querystrong: &QueryStrong<'_>) -> Self {
+        self.path_and_query(path_and_query(path, querystrong))
+    }
+}
+
+fn path_and_query(path: &str, querystrong: &QueryStrong<'_>) -> String {
+    if querystrong.is_empty() {
+        path.to_string()
+    } else {
+        format!("{path}?{querystrong}")
+    }
+}
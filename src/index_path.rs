@@ -1,8 +1,8 @@
 use memchr::memchr2;
 
-use crate::{Error, Indexer, Result};
-use std::{
-    collections::VecDeque,
+use crate::{Error, Indexer, Result, Value};
+use alloc::{borrow::Cow, collections::VecDeque, string::String, vec::Vec};
+use core::{
     convert::TryFrom,
     fmt::{self, Display, Formatter},
     ops::{Deref, DerefMut},
@@ -66,10 +66,75 @@ impl<'a> IndexPath<'a> {
         IndexPath(self.0.into_iter().map(Indexer::into_owned).collect())
     }
 
-    pub fn parse(mut s: &'a str) -> Result<'a, Self> {
+    /// Bracket notation without any percent-encoding, as the key appears to
+    /// a server after it has decoded the query string.
+    #[cfg(feature = "url")]
+    pub(crate) fn to_decoded_string(&self) -> String {
+        let mut out = String::new();
+        for (i, indexer) in self.0.iter().enumerate() {
+            if i > 0 {
+                out.push('[');
+            }
+            match indexer {
+                Indexer::Number(n) => out.push_str(&format!("{n}")),
+                Indexer::String(s) => out.push_str(s),
+                Indexer::Empty => {}
+            }
+            if i > 0 {
+                out.push(']');
+            }
+        }
+        out
+    }
+
+    pub fn parse(s: &'a str) -> Result<'a, Self> {
+        Self::parse_with(s, crate::decode)
+    }
+
+    /// Like [`parse`](IndexPath::parse), but with `decode` turning string
+    /// segments into keys.
+    pub(crate) fn parse_with(
+        s: &'a str,
+        mut decode: impl FnMut(&'a str) -> Cow<'a, str>,
+    ) -> Result<'a, Self> {
+        let mut v = VecDeque::new();
+        Self::split(s, |segment| {
+            v.push_back(Indexer::from_segment(segment, &mut decode));
+            Ok(())
+        })?;
+        Ok(IndexPath(v))
+    }
+
+    /// Replace `self` with the path of the raw query-string key `s`, reusing
+    /// its buffer, and reject it if it is nested deeper than
+    /// [`Value::MAX_DEPTH`].  This is the step that turns each key into a path
+    /// while parsing.  Splitting stops at the first segment past the limit, so
+    /// a hostile key costs no more than a key of the largest accepted depth.
+    pub(crate) fn parse_key(
+        &mut self,
+        s: &'a str,
+        mut decode: impl FnMut(&'a str) -> Cow<'a, str>,
+    ) -> Result<'a, ()> {
+        self.0.clear();
+        Self::split(s, |segment| {
+            if self.0.len() == Value::MAX_DEPTH {
+                return Err(Error::TooDeep(Value::MAX_DEPTH + 1));
+            }
+            self.0
+                .push_back(Indexer::from_segment(segment, &mut decode));
+            Ok(())
+        })
+    }
+
+    /// Call `push` with each raw, still-encoded segment of `s` in order,
+    /// stopping at the first malformed bracket or the first error `push`
+    /// returns.
+    pub(crate) fn split(
+        mut s: &'a str,
+        mut push: impl FnMut(&'a str) -> Result<'a, ()>,
+    ) -> Result<'a, ()> {
         let orig = s;
         use IndexPathState::*;
-        let mut v = VecDeque::new();
         let mut state = Start;
         loop {
             if s.is_empty() || state == End {
@@ -88,29 +153,19 @@ impl<'a> IndexPath<'a> {
                 (None, s)
             };
 
-            let mut push = || {
-                v.push_back(if current.is_empty() {
-                    Indexer::Empty
-                } else if let Ok(u) = current.parse::<usize>() {
-                    Indexer::Number(u)
-                } else {
-                    Indexer::from(current)
-                });
-            };
-
             state = match (state, divider) {
                 (_, None) => {
-                    push();
+                    push(current)?;
                     End
                 }
 
                 (Start, Some('[')) => {
-                    push();
+                    push(current)?;
                     BracketOpen
                 }
 
                 (BracketOpen, Some(']')) => {
-                    push();
+                    push(current)?;
                     BracketClose
                 }
 
@@ -120,7 +175,7 @@ impl<'a> IndexPath<'a> {
             };
         }
 
-        Ok(IndexPath(v))
+        Ok(())
     }
 }
 
@@ -182,6 +237,12 @@ where
     }
 }
 
+impl<'a> FromIterator<Indexer<'a>> for IndexPath<'a> {
+    fn from_iter<T: IntoIterator<Item = Indexer<'a>>>(iter: T) -> Self {
+        Self(iter.into_iter().collect())
+    }
+}
+
 impl Display for IndexPath<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         let mut iter = self.0.iter();
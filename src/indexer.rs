@@ -1,7 +1,8 @@
-use std::{
+use alloc::{
     borrow::Cow,
-    fmt::{Display, Formatter, Result},
+    string::{String, ToString},
 };
+use core::fmt::{Display, Formatter, Result};
 
 /// A single segment of an [`IndexPath`](crate::IndexPath).
 ///
@@ -29,6 +30,21 @@ impl<'a> Indexer<'a> {
             Indexer::Empty => Indexer::Empty,
         }
     }
+
+    /// Classify one raw segment of a key, calling `decode` only if it is a
+    /// string key.
+    pub(crate) fn from_segment(
+        segment: &'a str,
+        decode: impl FnOnce(&'a str) -> Cow<'a, str>,
+    ) -> Self {
+        if segment.is_empty() {
+            Indexer::Empty
+        } else if let Ok(n) = segment.parse::<usize>() {
+            Indexer::Number(n)
+        } else {
+            Indexer::String(decode(segment))
+        }
+    }
 }
 
 impl From<usize> for Indexer<'_> {
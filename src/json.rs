@@ -0,0 +1,158 @@
+use crate::{Error, QueryStrong, Result, Value, value::try_densify};
+use alloc::{borrow::Cow, collections::BTreeMap, string::ToString};
+use serde_json::Value as Json;
+
+/// Converts to JSON with the same shape as the `serde` feature's `Serialize`
+/// implementation: maps become objects, dense lists arrays, strings strings,
+/// and [`Value::Empty`] `null`.
+///
+/// A [`Value::SparseList`] becomes an object keyed by the decimal index
+/// (`{"0": "x", "5": "y"}`) rather than an array padded with nulls, so that a
+/// large index cannot inflate the output.
+impl From<Value<'_>> for Json {
+    fn from(value: Value<'_>) -> Self {
+        match value {
+            Value::Map(m) => Json::Object(
+                m.into_iter()
+                    .map(|(k, v)| (k.into_owned(), v.into()))
+                    .collect(),
+            ),
+            Value::List(l) => Json::Array(l.into_iter().map(Json::from).collect()),
+            Value::SparseList(m) => Json::Object(
+                m.into_iter()
+                    .map(|(n, v)| (n.to_string(), v.into()))
+                    .collect(),
+            ),
+            Value::String(s) => Json::String(s.into_owned()),
+            Value::Encoded(e) => Json::String(e.into_cow().into_owned()),
+            Value::Empty => Json::Null,
+        }
+    }
+}
+
+impl From<QueryStrong<'_>> for Json {
+    fn from(querystrong: QueryStrong<'_>) -> Self {
+        querystrong.value.into()
+    }
+}
+
+/// Converts from JSON, the inverse of `From<Value> for serde_json::Value`.
+///
+/// Query strings have no types besides strings, so booleans become `"true"`
+/// or `"false"` and numbers their `serde_json` display form (`2`, `-1.5`).
+/// `null` becomes [`Value::Empty`].  An object whose keys are all decimal
+/// indices is read back as a list, dense if the indices are contiguous from
+/// zero and sparse otherwise, exactly as `a[0]=..&a[5]=..` would parse.
+///
+/// Nesting deeper than [`Value::MAX_DEPTH`] is rejected with
+/// [`Error::TooDeep`], as parsing rejects such keys, so untrusted JSON cannot
+/// exhaust the stack.  JSON can also be passed straight to [`Value::append`].
+impl<'a> TryFrom<Json> for Value<'a> {
+    type Error = Error<'a>;
+
+    fn try_from(json: Json) -> Result<'a, Self> {
+        from_json(json, 0)
+    }
+}
+
+// `depth` is the length of the path at which `json` will sit
+fn from_json<'a>(json: Json, depth: usize) -> Result<'a, Value<'a>> {
+    if depth > Value::MAX_DEPTH {
+        return Err(Error::TooDeep(depth));
+    }
+    let depth = depth + 1;
+    Ok(match json {
+        Json::Null => Value::Empty,
+        Json::Bool(b) => Value::String(Cow::Borrowed(if b { "true" } else { "false" })),
+        Json::Number(n) => Value::String(Cow::Owned(n.to_string())),
+        Json::String(s) => Value::String(Cow::Owned(s)),
+        Json::Array(a) => Value::List(
+            a.into_iter()
+                .map(|v| from_json(v, depth))
+                .collect::<Result<'a, _>>()?,
+        ),
+        Json::Object(o) if !o.is_empty() && o.keys().all(|k| index(k).is_some()) => {
+            let mut list = BTreeMap::new();
+            for (k, v) in o {
+                if let Some(n) = index(&k) {
+                    list.insert(n, from_json(v, depth)?);
+                }
+            }
+            try_densify(list)
+        }
+        Json::Object(o) => Value::Map(
+            o.into_iter()
+                .map(|(k, v)| Ok((Cow::Owned(k), from_json(v, depth)?)))
+                .collect::<Result<'a, _>>()?,
+        ),
+    })
+}
+
+fn index(key: &str) -> Option<usize> {
+    if key.bytes().all(|b| b.is_ascii_digit()) && (key == "0" || !key.starts_with('0')) {
+        key.parse().ok()
+    } else {
+        None
+    }
+}
+
+fn leaf_eq(s: &str, json: &Json) -> bool {
+    match json {
+        Json::String(j) => s == j,
+        Json::Bool(b) => s == if *b { "true" } else { "false" },
+        Json::Number(n) => s == n.to_string(),
+        _ => false,
+    }
+}
+
+/// Compares structurally, using the same mapping as the conversions: a string
+/// equals a JSON boolean or number whose encoding it matches, and a sparse
+/// list equals an object keyed by its indices.
+///
+/// ```
+/// use querystrong::QueryStrong;
+/// use serde_json::json;
+///
+/// let qs = QueryStrong::parse("filter[status][]=open&filter[page]=2&flag");
+/// assert_eq!(qs["filter"], json!({"status": ["open"], "page": 2}));
+/// assert_eq!(qs["flag"], json!(null));
+/// ```
+impl PartialEq<Json> for Value<'_> {
+    fn eq(&self, json: &Json) -> bool {
+        match (self, json) {
+            (Value::Map(m), Json::Object(o)) => {
+                m.len() == o.len() && m.iter().all(|(k, v)| o.get(&**k).is_some_and(|j| v == j))
+            }
+            (Value::List(l), Json::Array(a)) => {
+                l.len() == a.len() && l.iter().zip(a).all(|(v, j)| v == j)
+            }
+            (Value::SparseList(m), Json::Object(o)) => {
+                m.len() == o.len()
+                    && m.iter()
+                        .all(|(n, v)| o.get(&n.to_string()).is_some_and(|j| v == j))
+            }
+            (Value::String(s), json) => leaf_eq(s, json),
+            (Value::Encoded(e), json) => leaf_eq(e.as_str(), json),
+            (Value::Empty, Json::Null) => true,
+            _ => false,
+        }
+    }
+}
+
+impl PartialEq<Value<'_>> for Json {
+    fn eq(&self, value: &Value<'_>) -> bool {
+        value == self
+    }
+}
+
+impl PartialEq<Json> for QueryStrong<'_> {
+    fn eq(&self, json: &Json) -> bool {
+        **self == *json
+    }
+}
+
+impl PartialEq<QueryStrong<'_>> for Json {
+    fn eq(&self, querystrong: &QueryStrong<'_>) -> bool {
+        **querystrong == *self
+    }
+}
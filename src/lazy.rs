@@ -0,0 +1,102 @@
+use crate::{IndexPath, Indexer, QueryStrong, Value, decode, percent_coding, split_pairs};
+use alloc::borrow::Cow;
+use core::convert::TryInto;
+
+/// Look up a single parameter of the query string `s` without parsing the rest
+/// of it.
+///
+/// Returns the same [`Value`] that `QueryStrong::parse(s).get(key)` would
+/// find, or `None` if that would find nothing.  Only the pairs whose key
+/// starts with the same first segment as `key` are parsed; the others are
+/// skipped after comparing that segment.  Errors in the parsed pairs are
+/// ignored, as [`QueryStrong::parse`] skips them too.
+///
+/// Each call scans the whole input.  To look up several parameters, use a
+/// [`LazyQuery`], or parse once if most of the input will be read anyway.
+///
+/// ```
+/// use querystrong::{Value, lookup};
+///
+/// let s = "user[name]=jacob&user[roles][]=admin&format=json&debug";
+/// assert_eq!(lookup(s, "format").unwrap(), "json");
+/// assert_eq!(lookup(s, "user[roles][0]").unwrap(), "admin");
+/// assert_eq!(lookup(s, "debug"), Some(Value::Empty));
+/// assert_eq!(lookup(s, "verbose"), None);
+/// ```
+pub fn lookup<'a, 'k>(s: &'a str, key: impl TryInto<IndexPath<'k>>) -> Option<Value<'a>> {
+    LazyQuery::new(s).get(key)
+}
+
+/// A query string that is parsed one lookup at a time.
+///
+/// `LazyQuery` holds only the input.  Each lookup works like [`lookup`]:
+/// it scans the input, parses just the pairs that share the key's first
+/// segment, and returns the value a full [`QueryStrong::parse`] would have
+/// at that key.  This is cheaper than a full parse when middleware reads one
+/// or two parameters of a large query string.
+///
+/// ```
+/// use querystrong::LazyQuery;
+///
+/// let query = LazyQuery::new("filter[status][]=open&filter[status][]=closed&q=rust+lang");
+/// assert_eq!(query.get_str("q").as_deref(), Some("rust lang"));
+/// assert_eq!(query.get("filter[status]").unwrap().len(), 2);
+/// assert!(!query.contains_key("page"));
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct LazyQuery<'a> {
+    input: &'a str,
+}
+
+impl<'a> LazyQuery<'a> {
+    /// Wrap `s`, doing no work until the first lookup.
+    pub fn new(s: &'a str) -> Self {
+        Self { input: s }
+    }
+
+    /// The query string this looks up parameters in.
+    pub fn input(&self) -> &'a str {
+        self.input
+    }
+
+    /// The value at `key`, as `QueryStrong::parse(self.input()).get(key)`
+    /// would find it.
+    pub fn get<'k>(&self, key: impl TryInto<IndexPath<'k>>) -> Option<Value<'a>> {
+        let path = key.try_into().ok()?;
+        // The top level is a map, so only a string key can be found
+        let Some(Indexer::String(name)) = path.front() else {
+            return None;
+        };
+
+        let pairs = split_pairs(self.input).filter(|(k, _)| first_segment_is(k, name));
+        let mut querystrong = QueryStrong::new();
+        querystrong.append_pairs(self.input, pairs, false, decode);
+        querystrong.value.take(path)
+    }
+
+    /// The string at `key`, decoded, or `None` if there is none.
+    pub fn get_str<'k>(&self, key: impl TryInto<IndexPath<'k>>) -> Option<Cow<'a, str>> {
+        match self.get(key)? {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Whether the query string has a parameter at `key`, even one without a
+    /// value such as `?debug`.
+    pub fn contains_key<'k>(&self, key: impl TryInto<IndexPath<'k>>) -> bool {
+        self.get(key).is_some()
+    }
+}
+
+// Whether the first segment of the raw, still-encoded key `key` decodes to
+// `name`.  Compares without decoding when the segment has nothing to decode.
+fn first_segment_is(key: &str, name: &str) -> bool {
+    let end = memchr::memchr2(b'[', b']', key.as_bytes()).unwrap_or(key.len());
+    let first = &key[..end];
+    if percent_coding::needs_decoding(first) {
+        decode(first) == name
+    } else {
+        first == name
+    }
+}
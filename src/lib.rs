@@ -1,3 +1,4 @@
+#![no_std]
 #![forbid(unsafe_code, future_incompatible)]
 #![deny(
     missing_debug_implementations,
@@ -46,7 +47,14 @@
 //! regions that do not require percent-decoding or `+`-as-space substitution
 //! are never copied.  The lifetime `'a` on [`QueryStrong<'a>`] and [`Value<'a>`]
 //! tracks this borrow.  Call [`QueryStrong::into_owned`] to obtain a `'static`
-//! value that owns all its strings.
+//! value that owns all its strings, or, with the `yoke` feature, parse into an
+//! `OwnedQueryStrong`, which keeps the input alongside the parse instead of
+//! copying it.
+//!
+//! To read one or two parameters without building the whole tree, use
+//! [`lookup`] or a [`LazyQuery`], which parse only the pairs under the
+//! requested key.  [`pairs`] goes further and streams the parsed pairs of
+//! an input of any size without building a tree at all.
 //!
 //! ## List variants
 //!
@@ -55,8 +63,27 @@
 //! `BTreeMap`, which is memory-safe for large indices like `a[999999]=v`.  A
 //! sparse list collapses back to a dense list automatically once its indices
 //! become contiguous from zero.
-
-use std::{
+//!
+//! ## `no_std`
+//!
+//! The `std` feature is on by default.  Without it the crate is `#![no_std]`
+//! and needs only `alloc`: [`Value`], [`IndexPath`], [`QueryStrong`] and
+//! [`ParseErrors`] work as usual, and the errors implement
+//! [`core::error::Error`].  The `serde`, `serde_json` and `yoke` features are
+//! available without `std`.  The `regex`, `signing`, `url`, `http`, `axum`,
+//! `trillium` and `reqwest` features enable `std`.
+
+#[macro_use]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+use alloc::{
+    borrow::Cow,
+    boxed::Box,
+    string::{String, ToString},
+};
+use core::{
     convert::{Infallible, TryFrom, TryInto},
     fmt::{self, Debug, Display, Formatter, Write},
     ops::{Deref, DerefMut, Index},
@@ -72,11 +99,91 @@ pub use index_path::IndexPath;
 mod value;
 pub use value::Value;
 
+mod encoded;
+pub use encoded::EncodedStr;
+
+mod arena;
+pub use arena::Arena;
+
+mod storage;
+pub use storage::StringStorage;
+
+mod lazy;
+pub use lazy::{LazyQuery, lookup};
+
+mod pairs;
+pub(crate) use pairs::split_pairs;
+pub use pairs::{Pairs, pairs};
+
 mod error;
 pub use error::{Error, ParseErrors, ParseResult, Result};
 
+mod visit;
+pub use visit::{
+    Visit, VisitMut, walk_list, walk_list_mut, walk_map, walk_map_mut, walk_sparse_list,
+    walk_sparse_list_mut, walk_value, walk_value_mut,
+};
+
+mod flatten;
+pub use flatten::PathNotation;
+
+mod permit;
+
+mod canonical;
+pub use canonical::CanonicalOptions;
+
+mod normalize;
+pub use normalize::{oauth1_normalized_parameters, sigv4_canonical};
+
+mod redact;
+pub use redact::{Redacted, RedactionPolicy};
+
+mod schema;
+pub use schema::{Field, Schema, Violation, ViolationKind, Violations};
+
+#[cfg(feature = "serde_json")]
+mod json;
+
+#[cfg(feature = "signing")]
+mod signing;
+#[cfg(feature = "signing")]
+pub use signing::{SignatureError, Signer};
+
+#[cfg(feature = "url")]
+mod url;
+#[cfg(feature = "url")]
+pub use crate::url::{FormSerializerExt, UrlExt};
+
+#[cfg(feature = "http")]
+mod http;
+#[cfg(feature = "http")]
+pub use crate::http::{RequestExt, UriBuilderExt, UriExt};
+
+#[cfg(feature = "axum")]
+mod de;
+
+#[cfg(feature = "axum")]
+mod axum;
+#[cfg(feature = "axum")]
+pub use crate::axum::{QueryStrongRejection, StrongForm, StrongQuery};
+
+#[cfg(feature = "trillium")]
+mod trillium;
+#[cfg(feature = "trillium")]
+pub use crate::trillium::{ConnExt, OnParseError, QueryStrongHandler};
+
+#[cfg(feature = "reqwest")]
+mod reqwest;
+#[cfg(feature = "reqwest")]
+pub use crate::reqwest::RequestBuilderExt;
+
+#[cfg(feature = "yoke")]
+mod owned;
+#[cfg(feature = "yoke")]
+pub use owned::OwnedQueryStrong;
+
 mod percent_coding;
-pub(crate) use percent_coding::{decode, encode};
+pub(crate) use percent_coding::{decode, encode, encode_rfc3986};
 
 /// A parsed query string.
 ///
@@ -86,9 +193,10 @@ pub(crate) use percent_coding::{decode, encode};
 /// to detach from the original string.
 ///
 /// The top-level value is always a [`Value::Map`].  Because `QueryStrong` implements
-/// [`Deref<Target = Value>`](std::ops::Deref), all [`Value`] methods are available
+/// [`Deref<Target = Value>`](core::ops::Deref), all [`Value`] methods are available
 /// directly on a `QueryStrong`.
-#[derive(Clone, PartialEq, Eq)]
+#[derive(Clone)]
+#[cfg_attr(feature = "yoke", derive(yoke::Yokeable))]
 pub struct QueryStrong<'a> {
     value: Value<'a>,
     errors: Option<ParseErrors<'a>>,
@@ -128,37 +236,99 @@ impl<'a> QueryStrong<'a> {
     /// assert_eq!(qs.errors().unwrap().errors().len(), 1);
     /// ```
     pub fn parse(s: &'a str) -> Self {
-        let mut querystrong = QueryStrong::new();
-        let mut remaining = s;
+        Self::parse_with(s, false, decode)
+    }
 
-        while !remaining.is_empty() {
-            let kv;
+    /// Like [`parse`](QueryStrong::parse), but defers percent-decoding of
+    /// values until they are read.
+    ///
+    /// Values that contain `%` or `+` are stored as [`Value::Encoded`] slices
+    /// of `s` instead of being decoded into a new `String`, so parsing
+    /// allocates nothing for values at all.  This suits large forms where only
+    /// a few parameters are read.  Keys are still decoded, since they are
+    /// needed to build the tree.
+    ///
+    /// [`Value::as_str`] and [`get_str`](Value::get_str) decode an encoded
+    /// value on first access and cache the result.  Otherwise the result behaves like that of
+    /// `parse`: it compares equal to it, and displays and serializes the same.
+    ///
+    /// ```
+    /// use querystrong::{QueryStrong, Value};
+    ///
+    /// let qs = QueryStrong::parse_lazy("q=caf%C3%A9&page=2");
+    /// assert_eq!(qs["q"], Value::Encoded("caf%C3%A9".into()));
+    /// assert_eq!(qs["q"].as_str(), Some("café"));
+    /// assert_eq!(qs.get_str("page"), Some("2"));
+    /// assert_eq!(qs, QueryStrong::parse("q=caf%C3%A9&page=2"));
+    /// ```
+    pub fn parse_lazy(s: &'a str) -> Self {
+        Self::parse_with(s, true, decode)
+    }
 
-            if let Some(ampersand_index) = memchr::memchr(b'&', remaining.as_bytes()) {
-                kv = &remaining[..ampersand_index];
-                remaining = &remaining[ampersand_index + 1..];
-            } else {
-                kv = remaining;
-                remaining = "";
+    /// Like [`parse`](QueryStrong::parse), but decodes keys and values into
+    /// `arena` rather than allocating a `String` for each.
+    ///
+    /// The result borrows from both `s` and `arena`, so the arena stays
+    /// borrowed until the result is dropped; call
+    /// [`into_owned`](QueryStrong::into_owned) to keep the result longer.  See
+    /// [`Arena`] for an example.
+    pub fn parse_in(s: &'a str, arena: &'a mut Arena) -> Self {
+        arena.fill(s);
+        let arena: &'a Arena = arena;
+        let mut decoded = arena.segments().peekable();
+        Self::parse_with(s, false, |raw| {
+            if !percent_coding::needs_decoding(raw) {
+                return Cow::Borrowed(raw);
+            }
+            // Segments are matched by position, so one the arena skipped can
+            // never be given another segment's text
+            let offset = arena::offset_in(s, raw);
+            while decoded.next_if(|&(start, _)| start < offset).is_some() {}
+            match decoded.next_if(|&(start, _)| start == offset) {
+                Some((_, segment)) => Cow::Borrowed(segment),
+                None => decode(raw),
             }
+        })
+    }
 
-            if !kv.is_empty() {
-                let (k, v) = if let Some(equals_index) = memchr::memchr(b'=', kv.as_bytes()) {
-                    (&kv[..equals_index], Some(&kv[equals_index + 1..]))
-                } else {
-                    (kv, None)
-                };
+    /// Parse `s`, turning string segments into keys and values with `decode`.
+    /// If `lazy`, values that need decoding are kept as [`Value::Encoded`]
+    /// instead.
+    fn parse_with(s: &'a str, lazy: bool, decode: impl FnMut(&'a str) -> Cow<'a, str>) -> Self {
+        let mut querystrong = QueryStrong::new();
+        querystrong.append_pairs(s, split_pairs(s), lazy, decode);
+        querystrong
+    }
 
-                if let Err(e) = IndexPath::parse(k).and_then(|k| querystrong.append(k, v)) {
-                    querystrong
-                        .errors
-                        .get_or_insert_with(|| ParseErrors::new(s))
-                        .push(e);
-                }
+    // Appends `pairs`, raw pairs of `s`, to `self`, which must be clear.
+    fn append_pairs(
+        &mut self,
+        s: &'a str,
+        pairs: impl Iterator<Item = (&'a str, Option<&'a str>)>,
+        lazy: bool,
+        mut decode: impl FnMut(&'a str) -> Cow<'a, str>,
+    ) {
+        // One key buffer for every pair rather than one per pair
+        let mut path = IndexPath::default();
+        let mut errors = self.errors.take().map(|errors| errors.recycle(s));
+
+        for (k, v) in pairs {
+            let result = path.parse_key(k, &mut decode).and_then(|()| {
+                let value = match v {
+                    None => Value::Empty,
+                    Some(v) if lazy && percent_coding::needs_decoding(v) => {
+                        Value::Encoded(EncodedStr::new(v))
+                    }
+                    Some(v) => Value::String(decode(v)),
+                };
+                self.value.append_path(&mut path, value)
+            });
+            if let Err(e) = result {
+                errors.get_or_insert_with(|| ParseErrors::new(s)).push(e);
             }
         }
 
-        querystrong
+        self.errors = errors;
     }
 
     /// Parse a query string, returning `Err` if any part of the input is invalid.
@@ -176,7 +346,81 @@ impl<'a> QueryStrong<'a> {
     /// `Some(_)` means at least one segment was skipped; the successfully-parsed
     /// portions of the input are still accessible on `self`.
     pub fn errors(&self) -> Option<&ParseErrors<'a>> {
-        self.errors.as_ref()
+        // A cleared error list is kept for reuse but reads as no errors
+        self.errors.as_ref().filter(|errors| !errors.is_empty())
+    }
+
+    /// Remove every parameter and parse error, leaving an empty map.
+    ///
+    /// The error list's allocation is kept for the next
+    /// [`parse_into`](QueryStrong::parse_into).
+    pub fn clear(&mut self) {
+        match &mut self.value {
+            Value::Map(m) => m.clear(),
+            value => *value = Value::new_map(),
+        }
+        if let Some(errors) = &mut self.errors {
+            errors.clear();
+        }
+    }
+
+    /// Replace the contents of `self` with the permissive parse of `s`, as
+    /// [`parse`](QueryStrong::parse) would produce it.
+    ///
+    /// Meant for a loop that parses one query after another: the error list
+    /// is reused rather than reallocated, and keys are parsed into a single
+    /// buffer rather than one per pair.  The map itself starts empty, since a
+    /// `BTreeMap` frees its nodes when cleared.  To reuse a `QueryStrong`
+    /// across inputs that do not outlive it, see
+    /// [`recycle`](QueryStrong::recycle).
+    ///
+    /// ```
+    /// use querystrong::QueryStrong;
+    ///
+    /// let mut qs = QueryStrong::parse("a=1&a[b]=2");
+    /// assert!(qs.errors().is_some());
+    ///
+    /// qs.parse_into("page=2");
+    /// assert_eq!(qs, QueryStrong::parse("page=2"));
+    /// assert!(qs.errors().is_none());
+    /// ```
+    pub fn parse_into(&mut self, s: &'a str) {
+        self.clear();
+        self.append_pairs(s, split_pairs(s), false, decode);
+    }
+
+    /// Clear `self` for use with an input of any lifetime, keeping the same
+    /// allocations as [`clear`](QueryStrong::clear).
+    ///
+    /// This lets a worker keep one `QueryStrong<'static>` and parse each
+    /// request's borrowed query string into it:
+    ///
+    /// ```
+    /// use querystrong::QueryStrong;
+    ///
+    /// struct Worker {
+    ///     spare: QueryStrong<'static>,
+    /// }
+    ///
+    /// impl Worker {
+    ///     fn handle(&mut self, query: &str) -> Option<usize> {
+    ///         let mut qs = std::mem::take(&mut self.spare).recycle();
+    ///         qs.parse_into(query);
+    ///         let page = qs.get_str("page").and_then(|page| page.parse().ok());
+    ///         self.spare = qs.recycle();
+    ///         page
+    ///     }
+    /// }
+    ///
+    /// let mut worker = Worker { spare: QueryStrong::new() };
+    /// assert_eq!(worker.handle(&String::from("page=2")), Some(2));
+    /// assert_eq!(worker.handle(&String::from("page=3&q=x")), Some(3));
+    /// ```
+    pub fn recycle<'b>(self) -> QueryStrong<'b> {
+        QueryStrong {
+            value: Value::new_map(),
+            errors: self.errors.map(|errors| errors.recycle("")),
+        }
     }
 
     /// Convert this `QueryStrong<'a>` into a `QueryStrong<'static>` by cloning
@@ -191,6 +435,12 @@ impl<'a> QueryStrong<'a> {
         }
     }
 
+    /// Consume `self`, returning the parsed [`Value`] and discarding any parse
+    /// errors.
+    pub fn into_value(self) -> Value<'a> {
+        self.value
+    }
+
     /// Consume `self`, returning `Ok(self)` if there were no parse errors or
     /// `Err(ParseErrors)` if any were accumulated.
     ///
@@ -198,8 +448,8 @@ impl<'a> QueryStrong<'a> {
     /// returned value has a clean error state.
     pub fn into_result(mut self) -> ParseResult<'a, Self> {
         match self.errors.take() {
-            Some(error) => Err(error),
-            None => Ok(self),
+            Some(errors) if !errors.is_empty() => Err(errors),
+            _ => Ok(self),
         }
     }
 
@@ -211,12 +461,24 @@ impl<'a> QueryStrong<'a> {
     pub fn unwrap(self) -> Self {
         self.into_result().unwrap()
     }
+
+    // Every parameter as `Display` writes it, with the key in bracket
+    // notation and both key and value percent-decoded.  `None` is a bare key.
+    #[cfg(feature = "url")]
+    pub(crate) fn decoded_pairs(&self) -> impl Iterator<Item = (String, Option<String>)> + '_ {
+        self.into_iter().map(|(key, value)| {
+            (
+                key.to_decoded_string(),
+                value.map(|value| decode(value).into_owned()),
+            )
+        })
+    }
 }
 
 impl FromStr for QueryStrong<'static> {
     type Err = Infallible;
 
-    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+    fn from_str(s: &str) -> core::result::Result<Self, Self::Err> {
         Ok(QueryStrong::parse(s).into_owned())
     }
 }
@@ -295,6 +557,14 @@ impl Display for QueryStrong<'_> {
     }
 }
 
+impl PartialEq for QueryStrong<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value && self.errors() == other.errors()
+    }
+}
+
+impl Eq for QueryStrong<'_> {}
+
 impl Debug for QueryStrong<'_> {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         Debug::fmt(&self.value, f)
@@ -315,7 +585,7 @@ impl serde::Serialize for QueryStrong<'_> {
     fn serialize<S: serde::Serializer>(
         &self,
         serializer: S,
-    ) -> std::result::Result<S::Ok, S::Error> {
+    ) -> core::result::Result<S::Ok, S::Error> {
         self.value.serialize(serializer)
     }
 }
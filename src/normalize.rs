@@ -0,0 +1,107 @@
+use crate::{decode, encode_rfc3986, split_pairs};
+use alloc::{string::String, vec::Vec};
+
+/// The canonical query string for an AWS Signature Version 4 request, given
+/// the raw query string `query` as it is sent.
+///
+/// Each `&`-separated pair of `query` is one parameter, taken literally: its
+/// name and value are percent-decoded and nothing else, so `a[b]=1` is signed
+/// as `a%5Bb%5D=1` and a repeated name such as `a=1&a=2` is signed once per
+/// value.  Names and values are percent-encoded with the RFC 3986 unreserved
+/// set (`A-Z a-z 0-9 - . _ ~`, with a space as `%20`), sorted by encoded name
+/// and then encoded value, and joined with `&`.  Bare names and empty values
+/// are written as `name=`.
+///
+/// To sign a [`QueryStrong`](crate::QueryStrong) built in code, pass its
+/// [`Display`](core::fmt::Display) form, which writes every list in bracket
+/// notation.
+///
+/// ```
+/// use querystrong::sigv4_canonical;
+/// assert_eq!(
+///     sigv4_canonical("prefix=photos/2024&max-keys=2&acl"),
+///     "acl=&max-keys=2&prefix=photos%2F2024"
+/// );
+/// ```
+pub fn sigv4_canonical(query: &str) -> String {
+    let mut pairs = encoded_pairs(query);
+    pairs.sort();
+    join(&pairs)
+}
+
+/// The normalized request parameters of an OAuth 1.0a signature base
+/// string ([RFC 5849 §3.4.1.3.2](https://www.rfc-editor.org/rfc/rfc5849#section-3.4.1.3.2)).
+///
+/// `query` is the raw query string and `body` the raw
+/// `application/x-www-form-urlencoded` body (pass `""` if there is none);
+/// `oauth` holds the decoded `oauth_*` protocol parameters.  Each pair of
+/// `query` and `body` is one parameter, with its name taken literally as for
+/// [`sigv4_canonical`].  Parameters from all three sources are kept even when
+/// names repeat, encoded with the RFC 3986 unreserved set, sorted by encoded
+/// name and then encoded value, and joined with `&`.  Any `oauth_signature`
+/// parameter is left out, as is `realm` in `oauth`.
+///
+/// ```
+/// use querystrong::oauth1_normalized_parameters;
+/// let normalized = oauth1_normalized_parameters(
+///     "b5=%3D%253D&a3=a&c%40=&a2=r%20b",
+///     "c2&a3=2+q",
+///     &[
+///         ("oauth_consumer_key", "9djdj82h48djs9d2"),
+///         ("oauth_token", "kkk9d7dh3k39sjv7"),
+///         ("oauth_signature_method", "HMAC-SHA1"),
+///         ("oauth_timestamp", "137131201"),
+///         ("oauth_nonce", "7d8f3e4a"),
+///     ],
+/// );
+/// assert_eq!(
+///     normalized,
+///     "a2=r%20b&a3=2%20q&a3=a&b5=%3D%253D&c%40=&c2=&oauth_consumer_key=9djdj82h48djs9d2\
+///      &oauth_nonce=7d8f3e4a&oauth_signature_method=HMAC-SHA1&oauth_timestamp=137131201\
+///      &oauth_token=kkk9d7dh3k39sjv7"
+/// );
+/// ```
+pub fn oauth1_normalized_parameters(query: &str, body: &str, oauth: &[(&str, &str)]) -> String {
+    let mut pairs = encoded_pairs(query);
+    pairs.extend(encoded_pairs(body));
+    pairs.retain(|(key, _)| key != "oauth_signature");
+    pairs.extend(
+        oauth
+            .iter()
+            .filter(|(key, _)| !matches!(*key, "oauth_signature" | "realm"))
+            .map(|(key, value)| {
+                (
+                    encode_rfc3986(key).into_owned(),
+                    encode_rfc3986(value).into_owned(),
+                )
+            }),
+    );
+    pairs.sort();
+    join(&pairs)
+}
+
+// Every pair of the raw query string `s`, name and value each decoded and
+// then re-encoded with the RFC 3986 unreserved set.
+fn encoded_pairs(s: &str) -> Vec<(String, String)> {
+    split_pairs(s)
+        .map(|(key, value)| {
+            (
+                encode_rfc3986(&decode(key)).into_owned(),
+                encode_rfc3986(&decode(value.unwrap_or_default())).into_owned(),
+            )
+        })
+        .collect()
+}
+
+fn join(pairs: &[(String, String)]) -> String {
+    let mut out = String::new();
+    for (key, value) in pairs {
+        if !out.is_empty() {
+            out.push('&');
+        }
+        out.push_str(key);
+        out.push('=');
+        out.push_str(value);
+    }
+    out
+}
@@ -0,0 +1,143 @@
+use crate::{IndexPath, ParseErrors, QueryStrong, Value};
+use alloc::{string::String, sync::Arc, vec::Vec};
+use bytes::Bytes;
+use core::{
+    convert::TryInto,
+    fmt::{self, Debug, Display, Formatter},
+    str::Utf8Error,
+};
+use yoke::Yoke;
+
+/// A [`QueryStrong`] that owns the input it borrows from.
+///
+/// [`QueryStrong::into_owned`] copies every borrowed string to get a
+/// `'static` value.  `OwnedQueryStrong` keeps the input buffer alive next to
+/// the parse instead, so no parsed string is copied and the result can still
+/// be stored in a struct or held across an `.await`.  It is `Send`, `Sync`
+/// and `'static`, and cloning it shares the input buffer.
+///
+/// [`get`](OwnedQueryStrong::get), [`get_str`](OwnedQueryStrong::get_str)
+/// and [`errors`](OwnedQueryStrong::errors) read the parse directly, and
+/// [`query_strong`](OwnedQueryStrong::query_strong) borrows all of it.
+/// `OwnedQueryStrong` does not implement `Deref`: the parse borrows from the
+/// input it owns, and a `Deref` target is a single type that cannot name the
+/// lifetime of that borrow without `unsafe` code.
+///
+/// Requires the `yoke` feature.
+///
+/// ```
+/// use querystrong::OwnedQueryStrong;
+///
+/// fn parse_later() -> OwnedQueryStrong {
+///     let input = String::from("user[name]=jacob&tags[]=a+b");
+///     OwnedQueryStrong::parse(input)
+/// }
+///
+/// let qs = parse_later();
+/// assert_eq!(qs.get_str("user[name]"), Some("jacob"));
+/// assert_eq!(qs.get_str("tags[0]"), Some("a b"));
+/// assert_eq!(qs.input(), "user[name]=jacob&tags[]=a+b");
+/// ```
+#[derive(Clone)]
+pub struct OwnedQueryStrong(Yoke<QueryStrong<'static>, Arc<String>>);
+
+impl OwnedQueryStrong {
+    /// Parse `s` with [`QueryStrong::parse`], taking ownership of it.  This
+    /// does not copy `s`.
+    pub fn parse(s: impl Into<String>) -> Self {
+        Self(Yoke::attach_to_cart(Arc::new(s.into()), |s: &String| {
+            QueryStrong::parse(s)
+        }))
+    }
+
+    /// Parse `bytes` with [`QueryStrong::parse`], taking ownership of them.
+    /// Returns an error if `bytes` is not UTF-8.
+    ///
+    /// The bytes are moved into a `String` first, which copies them only if
+    /// the buffer is shared with another `Bytes`.
+    ///
+    /// ```
+    /// use bytes::Bytes;
+    /// use querystrong::OwnedQueryStrong;
+    ///
+    /// let qs = OwnedQueryStrong::from_bytes(Bytes::from_static(b"a=1")).unwrap();
+    /// assert_eq!(qs.get_str("a"), Some("1"));
+    ///
+    /// assert!(OwnedQueryStrong::from_bytes(Bytes::from_static(b"a=\xff")).is_err());
+    /// ```
+    pub fn from_bytes(bytes: Bytes) -> Result<Self, Utf8Error> {
+        String::from_utf8(Vec::from(bytes))
+            .map(Self::parse)
+            .map_err(|e| e.utf8_error())
+    }
+
+    /// The parsed query string, borrowing from [`input`](OwnedQueryStrong::input).
+    pub fn query_strong(&self) -> &QueryStrong<'_> {
+        self.0.get()
+    }
+
+    /// The value at `key`, as [`Value::get`] finds it.
+    pub fn get<'b>(&self, key: impl TryInto<IndexPath<'b>>) -> Option<&Value<'_>> {
+        self.query_strong().get(key)
+    }
+
+    /// The string at `key`, as [`Value::get_str`] finds it.
+    pub fn get_str<'b>(&self, key: impl TryInto<IndexPath<'b>>) -> Option<&str> {
+        self.query_strong().get_str(key)
+    }
+
+    /// The errors from parsing, as [`QueryStrong::errors`] returns them.
+    pub fn errors(&self) -> Option<&ParseErrors<'_>> {
+        self.query_strong().errors()
+    }
+
+    /// The query string this was parsed from.
+    pub fn input(&self) -> &str {
+        self.0.backing_cart()
+    }
+
+    /// Detach from the input buffer, copying any borrowed strings.
+    pub fn into_owned(self) -> QueryStrong<'static> {
+        self.query_strong().clone().into_owned()
+    }
+}
+
+impl From<String> for OwnedQueryStrong {
+    fn from(s: String) -> Self {
+        Self::parse(s)
+    }
+}
+
+impl TryFrom<Bytes> for OwnedQueryStrong {
+    type Error = Utf8Error;
+    fn try_from(bytes: Bytes) -> Result<Self, Utf8Error> {
+        Self::from_bytes(bytes)
+    }
+}
+
+impl PartialEq for OwnedQueryStrong {
+    fn eq(&self, other: &Self) -> bool {
+        self.query_strong() == other.query_strong()
+    }
+}
+
+impl Eq for OwnedQueryStrong {}
+
+impl Display for OwnedQueryStrong {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(self.query_strong(), f)
+    }
+}
+
+impl Debug for OwnedQueryStrong {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Debug::fmt(self.query_strong(), f)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for OwnedQueryStrong {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.query_strong().serialize(serializer)
+    }
+}
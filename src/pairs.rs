@@ -0,0 +1,114 @@
+use crate::{IndexPath, Result, decode};
+use alloc::borrow::Cow;
+use core::{iter::FusedIterator, ops::Range};
+use memchr::memchr;
+
+/// Stream the pairs of the query string `s` without building a
+/// [`Value`](crate::Value) tree.
+///
+/// Yields one item per non-empty `&`-separated pair, in input order: the
+/// parsed key path, the decoded value (`None` for a bare key such as
+/// `?debug`), and the byte range of the whole pair in `s`.  A key that
+/// [`QueryStrong::parse`](crate::QueryStrong::parse) would skip as malformed
+/// or too deeply nested is reported as an `Err` item, and iteration goes on
+/// with the next pair.
+///
+/// Keys are parsed exactly as `QueryStrong::parse` parses them before
+/// appending.  Conflicts between pairs, such as `a=1&a[b]=2`, only arise when
+/// building a tree, so they are not reported here.  Memory use does not grow
+/// with the size of the input.
+///
+/// ```
+/// use querystrong::{IndexPath, pairs};
+///
+/// let s = "user[name]=J+R&debug&a[[b]=1&tags[]=x";
+/// let mut pairs = pairs(s);
+///
+/// let (path, value, range) = pairs.next().unwrap().unwrap();
+/// assert_eq!(path, IndexPath::parse("user[name]").unwrap());
+/// assert_eq!(value.as_deref(), Some("J R"));
+/// assert_eq!(&s[range], "user[name]=J+R");
+///
+/// let (path, value, range) = pairs.next().unwrap().unwrap();
+/// assert_eq!(path.to_string(), "debug");
+/// assert_eq!(value, None);
+/// assert_eq!(range, 15..20);
+///
+/// assert!(pairs.next().unwrap().is_err());
+/// assert_eq!(pairs.next().unwrap().unwrap().0.to_string(), "tags[]");
+/// assert!(pairs.next().is_none());
+/// ```
+pub fn pairs(s: &str) -> Pairs<'_> {
+    Pairs {
+        input: s,
+        ranges: PairRanges::new(s),
+    }
+}
+
+/// The iterator returned by [`pairs`].
+#[derive(Clone, Debug)]
+pub struct Pairs<'a> {
+    input: &'a str,
+    ranges: PairRanges<'a>,
+}
+
+impl<'a> Iterator for Pairs<'a> {
+    type Item = Result<'a, (IndexPath<'a>, Option<Cow<'a, str>>, Range<usize>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let range = self.ranges.next()?;
+        let (k, v) = split_pair(&self.input[range.clone()]);
+        let mut path = IndexPath::default();
+        Some(
+            path.parse_key(k, decode)
+                .map(|()| (path, v.map(decode), range)),
+        )
+    }
+}
+
+impl FusedIterator for Pairs<'_> {}
+
+/// Split a query string into its raw, still-encoded `key=value` pairs,
+/// skipping empty ones.  The value is `None` if there is no `=`.
+pub(crate) fn split_pairs(s: &str) -> impl Iterator<Item = (&str, Option<&str>)> {
+    PairRanges::new(s).map(move |range| split_pair(&s[range]))
+}
+
+fn split_pair(kv: &str) -> (&str, Option<&str>) {
+    match memchr(b'=', kv.as_bytes()) {
+        Some(equals_index) => (&kv[..equals_index], Some(&kv[equals_index + 1..])),
+        None => (kv, None),
+    }
+}
+
+// The byte ranges of the non-empty `&`-separated pairs of a query string
+#[derive(Clone, Debug)]
+struct PairRanges<'a> {
+    input: &'a str,
+    start: usize,
+}
+
+impl<'a> PairRanges<'a> {
+    fn new(input: &'a str) -> Self {
+        Self { input, start: 0 }
+    }
+}
+
+impl Iterator for PairRanges<'_> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Range<usize>> {
+        while self.start <= self.input.len() {
+            let rest = &self.input.as_bytes()[self.start..];
+            let end = memchr(b'&', rest).map_or(self.input.len(), |i| self.start + i);
+            let range = self.start..end;
+            self.start = end + 1;
+            if !range.is_empty() {
+                return Some(range);
+            }
+        }
+        None
+    }
+}
+
+impl FusedIterator for PairRanges<'_> {}
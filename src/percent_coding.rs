@@ -1,5 +1,5 @@
-use std::borrow::Cow;
-use std::fmt::Write;
+use alloc::{borrow::Cow, string::String, vec::Vec};
+use core::fmt::Write;
 
 fn from_hex(b: u8) -> Option<u8> {
     match b {
@@ -10,38 +10,92 @@ fn from_hex(b: u8) -> Option<u8> {
     }
 }
 
+/// Whether [`decode`] would change `s`.
+pub(crate) fn needs_decoding(s: &str) -> bool {
+    memchr::memchr2(b'%', b'+', s.as_bytes()).is_some()
+}
+
 pub(crate) fn decode<'a>(s: impl Into<Cow<'a, str>>) -> Cow<'a, str> {
     let s = s.into();
-    let Some(i) = s.bytes().position(|b| b == b'%' || b == b'+') else {
+    if !needs_decoding(&s) {
         return s;
-    };
+    }
     let mut out = Vec::with_capacity(s.len());
-    out.extend_from_slice(&s.as_bytes()[..i]);
+    decode_into(&s, &mut out);
+    Cow::Owned(String::from_utf8_lossy(&out).into_owned())
+}
+
+/// Append the decoded bytes of `s` to `out`.  The result may not be valid
+/// UTF-8; callers decide how to repair it.
+pub(crate) fn decode_into(s: &str, out: &mut Vec<u8>) {
+    decode_bytes(s, |b| out.push(b));
+}
+
+/// The length of [`decode`]`(s)`, computed without allocating.
+pub(crate) fn decoded_len(s: &str) -> usize {
+    if !needs_decoding(s) {
+        return s.len();
+    }
+    let mut buf = [0; 64];
+    let mut filled = 0;
+    let mut len = 0;
+    decode_bytes(s, |b| {
+        if filled == buf.len() {
+            let carried = lossy_len(&buf, false, &mut len);
+            buf.copy_within(filled - carried.., 0);
+            filled = carried;
+        }
+        buf[filled] = b;
+        filled += 1;
+    });
+    lossy_len(&buf[..filled], true, &mut len);
+    len
+}
+
+// Add the length `String::from_utf8_lossy` would give `bytes` to `len`.
+// Unless `last`, a sequence cut off at the end of `bytes` is not counted,
+// and its length is returned so it can be counted with the bytes after it.
+fn lossy_len(bytes: &[u8], last: bool, len: &mut usize) -> usize {
+    for chunk in bytes.utf8_chunks() {
+        *len += chunk.valid().len();
+        let invalid = chunk.invalid();
+        if invalid.is_empty() {
+            continue;
+        }
+        if !last && invalid.as_ptr_range().end == bytes.as_ptr_range().end {
+            return invalid.len();
+        }
+        *len += char::REPLACEMENT_CHARACTER.len_utf8();
+    }
+    0
+}
+
+/// Call `push` with each decoded byte of `s`.
+fn decode_bytes(s: &str, mut push: impl FnMut(u8)) {
     let bytes = s.as_bytes();
-    let mut i = i;
+    let mut i = 0;
     while i < bytes.len() {
         match bytes[i] {
             b'+' => {
-                out.push(b' ');
+                push(b' ');
                 i += 1;
             }
             b'%' if i + 2 < bytes.len() => match (from_hex(bytes[i + 1]), from_hex(bytes[i + 2])) {
                 (Some(hi), Some(lo)) => {
-                    out.push((hi << 4) | lo);
+                    push((hi << 4) | lo);
                     i += 3;
                 }
                 _ => {
-                    out.push(b'%');
+                    push(b'%');
                     i += 1;
                 }
             },
             b => {
-                out.push(b);
+                push(b);
                 i += 1;
             }
         }
     }
-    Cow::Owned(String::from_utf8_lossy(&out).into_owned())
 }
 
 pub(crate) fn encode(s: &str) -> Cow<'_, str> {
@@ -63,6 +117,25 @@ pub(crate) fn encode(s: &str) -> Cow<'_, str> {
     Cow::Owned(out)
 }
 
+/// Percent-encode everything outside the RFC 3986 unreserved set
+/// (`A-Z a-z 0-9 - . _ ~`), as required by AWS SigV4 and OAuth 1.0a.
+pub(crate) fn encode_rfc3986(s: &str) -> Cow<'_, str> {
+    let unreserved = |b: u8| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~');
+    let Some(i) = s.bytes().position(|b| !unreserved(b)) else {
+        return Cow::Borrowed(s);
+    };
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push_str(&s[..i]);
+    for b in s[i..].bytes() {
+        if unreserved(b) {
+            out.push(b as char);
+        } else {
+            let _ = write!(out, "%{b:02X}");
+        }
+    }
+    Cow::Owned(out)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -101,6 +174,13 @@ mod tests {
         assert!(matches!(encode("foo bar"), Cow::Owned(_)));
     }
 
+    #[test]
+    fn encode_rfc3986_charset() {
+        assert!(matches!(encode_rfc3986("a-b.c_d~e"), Cow::Borrowed(_)));
+        assert_eq!(encode_rfc3986("a b+c/d*"), "a%20b%2Bc%2Fd%2A");
+        assert_eq!(encode_rfc3986("💖"), "%F0%9F%92%96");
+    }
+
     #[test]
     fn encode_charset() {
         assert_eq!(encode("foo bar?"), "foo%20bar%3F");
@@ -0,0 +1,234 @@
+use crate::{Error, IndexPath, Indexer, Result, Value, value::try_densify};
+use alloc::{collections::BTreeMap, vec::Vec};
+use core::convert::TryInto;
+
+impl<'a> Value<'a> {
+    /// Return the value at `key`, or [`Error::MissingParameter`] if it is
+    /// absent or [empty](Value::is_empty).
+    ///
+    /// Intended to be chained with [`permit`](Value::permit), in the style of
+    /// Rails' strong parameters:
+    ///
+    /// ```
+    /// use querystrong::QueryStrong;
+    ///
+    /// # fn main() -> Result<(), querystrong::Error<'static>> {
+    /// let qs = QueryStrong::parse("user[name]=jacob&user[admin]=true&user[tags][]=rust");
+    /// let user = qs.require("user")?.permit(&["name", "tags[]"]);
+    /// assert_eq!(QueryStrong::from(user).to_string(), "name=jacob&tags[]=rust");
+    ///
+    /// assert!(qs.require("account").is_err());
+    /// # Ok(()) }
+    /// ```
+    pub fn require<'b, K>(&self, key: K) -> Result<'b, &Self>
+    where
+        K: TryInto<IndexPath<'b>>,
+        K::Error: Into<Error<'b>>,
+    {
+        let path = key.try_into().map_err(Into::into)?;
+        match self.get(path.clone()) {
+            Some(value) if !value.is_empty() => Ok(value),
+            _ => Err(Error::MissingParameter(path)),
+        }
+    }
+
+    /// Build a new value containing only the paths allowed by `filters`.
+    ///
+    /// Each filter is a path in bracket notation, relative to `self`:
+    ///
+    /// - `name` permits a string (or bare key) at `name`;
+    /// - `tags[]` permits a list of strings at `tags`;
+    /// - `address[street]` permits a string at `street` within a map at
+    ///   `address`;
+    /// - `rows[][id]` permits the `id` key of every map in a list at `rows`;
+    /// - `rows[0]` permits only the first element of a list.
+    ///
+    /// Anything else is dropped, including a map or list found where a filter
+    /// expects a string, so the result only ever has the shape the filters
+    /// describe.  Dropped list elements follow the same rules as
+    /// [`retain`](Value::retain), except that a dense list keeps its original
+    /// indices whenever a filter names one: permitting `tags[2]` keeps that
+    /// element at index 2, as a [`SparseList`](Value::SparseList) if the
+    /// elements before it are dropped.  Use [`permit_strict`](Value::permit_strict)
+    /// to reject unpermitted input instead, or
+    /// [`unpermitted`](Value::unpermitted) to log it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a filter is not a valid [`IndexPath`].
+    pub fn permit(&self, filters: &[&str]) -> Self {
+        self.permit_inner(filters, &mut None)
+    }
+
+    /// Like [`permit`](Value::permit), but return
+    /// [`Error::UnpermittedParameters`] listing every dropped path if anything
+    /// would be dropped.
+    pub fn permit_strict(&self, filters: &[&str]) -> Result<'a, Self> {
+        let mut unpermitted = Some(vec![]);
+        let value = self.permit_inner(filters, &mut unpermitted);
+        match unpermitted {
+            Some(unpermitted) if !unpermitted.is_empty() => {
+                Err(Error::UnpermittedParameters(unpermitted))
+            }
+            _ => Ok(value),
+        }
+    }
+
+    /// The paths, relative to `self`, that [`permit`](Value::permit) would
+    /// drop for `filters`.  A dropped subtree is reported once, at its root.
+    ///
+    /// ```
+    /// use querystrong::QueryStrong;
+    /// let qs = QueryStrong::parse("name=jacob&admin=true&tags[]=a&nested[x]=b");
+    /// let dropped: Vec<_> = qs
+    ///     .unpermitted(&["name", "tags[]", "nested"])
+    ///     .iter()
+    ///     .map(ToString::to_string)
+    ///     .collect();
+    /// assert_eq!(dropped, ["admin", "nested"]);
+    /// ```
+    pub fn unpermitted(&self, filters: &[&str]) -> Vec<IndexPath<'a>> {
+        let mut unpermitted = Some(vec![]);
+        self.permit_inner(filters, &mut unpermitted);
+        unpermitted.unwrap_or_default()
+    }
+
+    fn permit_inner(&self, filters: &[&str], unpermitted: &mut Option<Vec<IndexPath<'a>>>) -> Self {
+        let mut filters: Vec<IndexPath<'_>> = filters
+            .iter()
+            .map(|filter| IndexPath::parse(filter).expect("invalid permit filter"))
+            .collect();
+        let filters: Vec<&[Indexer<'_>]> = filters
+            .iter_mut()
+            .map(|filter| &*filter.make_contiguous())
+            .collect();
+
+        let mut path = IndexPath::default();
+        permit_value(self, &filters, &mut path, unpermitted).unwrap_or_else(Value::new_map)
+    }
+}
+
+// Returns the permitted part of `value`, or None if nothing under it is
+// permitted.  Every dropped node is recorded in `unpermitted` at the highest
+// path that was dropped as a whole.
+fn permit_value<'a>(
+    value: &Value<'a>,
+    filters: &[&[Indexer<'_>]],
+    path: &mut IndexPath<'a>,
+    unpermitted: &mut Option<Vec<IndexPath<'a>>>,
+) -> Option<Value<'a>> {
+    let leaf_permitted = filters.iter().any(|filter| filter.is_empty());
+    let has_heads = |list: bool| {
+        filters.iter().any(|filter| match filter.first() {
+            Some(Indexer::String(_)) => !list,
+            Some(Indexer::Number(_) | Indexer::Empty) => list,
+            None => false,
+        })
+    };
+
+    match value {
+        Value::String(_) | Value::Encoded(_) | Value::Empty if leaf_permitted => {
+            Some(value.clone())
+        }
+
+        Value::Map(m) if has_heads(false) => {
+            let mut out = BTreeMap::new();
+            for (key, v) in m {
+                path.push_back(Indexer::String(key.clone()));
+                let tails = tails(
+                    filters,
+                    |head| matches!(head, Indexer::String(k) if k == key),
+                );
+                if let Some(v) = permit_entry(v, &tails, path, unpermitted) {
+                    out.insert(key.clone(), v);
+                }
+                path.pop_back();
+            }
+            (!out.is_empty()).then_some(Value::Map(out))
+        }
+
+        Value::List(l) if has_heads(true) => {
+            let mut out = BTreeMap::new();
+            for (n, v) in l.iter().enumerate() {
+                path.push_back(Indexer::Number(n));
+                let tails = tails(filters, |head| list_head_matches(head, n));
+                if let Some(v) = permit_entry(v, &tails, path, unpermitted) {
+                    out.insert(n, v);
+                }
+                path.pop_back();
+            }
+
+            // An explicit index in a filter pins elements to their positions;
+            // otherwise the list closes up as it would under `retain`
+            let indexed = filters
+                .iter()
+                .any(|filter| matches!(filter.first(), Some(Indexer::Number(_))));
+            if out.is_empty() {
+                None
+            } else if indexed {
+                Some(try_densify(out))
+            } else {
+                Some(Value::List(out.into_values().collect()))
+            }
+        }
+
+        Value::SparseList(m) if has_heads(true) => {
+            let mut out = BTreeMap::new();
+            for (&n, v) in m {
+                path.push_back(Indexer::Number(n));
+                let tails = tails(filters, |head| list_head_matches(head, n));
+                if let Some(v) = permit_entry(v, &tails, path, unpermitted) {
+                    out.insert(n, v);
+                }
+                path.pop_back();
+            }
+            (!out.is_empty()).then(|| try_densify(out))
+        }
+
+        _ => {
+            record(path, unpermitted);
+            None
+        }
+    }
+}
+
+fn permit_entry<'a>(
+    value: &Value<'a>,
+    tails: &[&[Indexer<'_>]],
+    path: &mut IndexPath<'a>,
+    unpermitted: &mut Option<Vec<IndexPath<'a>>>,
+) -> Option<Value<'a>> {
+    if tails.is_empty() {
+        record(path, unpermitted);
+        None
+    } else {
+        permit_value(value, tails, path, unpermitted)
+    }
+}
+
+fn list_head_matches(head: &Indexer<'_>, n: usize) -> bool {
+    match head {
+        Indexer::Empty => true,
+        Indexer::Number(m) => *m == n,
+        Indexer::String(_) => false,
+    }
+}
+
+fn record<'a>(path: &IndexPath<'a>, unpermitted: &mut Option<Vec<IndexPath<'a>>>) {
+    if let Some(unpermitted) = unpermitted {
+        unpermitted.push(path.clone());
+    }
+}
+
+fn tails<'f, 'g>(
+    filters: &[&'g [Indexer<'f>]],
+    matches: impl Fn(&Indexer<'f>) -> bool,
+) -> Vec<&'g [Indexer<'f>]> {
+    filters
+        .iter()
+        .filter_map(|filter| match filter.split_first() {
+            Some((head, tail)) if matches(head) => Some(tail),
+            _ => None,
+        })
+        .collect()
+}
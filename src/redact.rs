@@ -0,0 +1,250 @@
+use crate::{IndexPath, Indexer, QueryStrong, Value, VisitMut, walk_value_mut};
+use alloc::{
+    borrow::Cow,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::{
+    convert::TryInto,
+    fmt::{self, Debug, Display, Formatter},
+};
+
+const SECRET_KEYS: &[&str] = &[
+    "auth",
+    "authorization",
+    "cookie",
+    "credit_card",
+    "cvc",
+    "cvv",
+    "pass",
+    "passwd",
+    "pin",
+    "pwd",
+    "session",
+    "sessionid",
+    "sig",
+    "signature",
+    "ssn",
+];
+
+const SECRET_PATTERNS: &[&str] = &[
+    "*password*",
+    "*secret*",
+    "*token*",
+    "*api_key*",
+    "*apikey*",
+    "*access_key*",
+    "*private_key*",
+];
+
+/// Which values [`QueryStrong::redacted`] masks.
+///
+/// A value is masked, along with everything below it, if any key on its path
+/// is one of the policy's [key names](RedactionPolicy::key) or matches one of
+/// its [glob patterns](RedactionPolicy::pattern), or if its path starts with
+/// one of the policy's [paths](RedactionPolicy::path).
+///
+/// [`RedactionPolicy::new`] starts from a built-in list of common secret
+/// names such as `password`, `token`, `api_key`, `client_secret` and
+/// `signature`; [`RedactionPolicy::empty`] starts from nothing.
+///
+/// ```
+/// use querystrong::{QueryStrong, RedactionPolicy};
+///
+/// let qs = QueryStrong::parse("user[name]=jacob&user[password]=hunter2&ssn=123&q=rust");
+/// let policy = RedactionPolicy::new().path("user[name]");
+/// assert_eq!(
+///     qs.redacted(&policy).to_string(),
+///     "q=rust&ssn=REDACTED&user[name]=REDACTED&user[password]=REDACTED"
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct RedactionPolicy {
+    keys: Vec<String>,
+    patterns: Vec<String>,
+    paths: Vec<IndexPath<'static>>,
+    mask: Cow<'static, str>,
+}
+
+impl Default for RedactionPolicy {
+    fn default() -> Self {
+        let policy = SECRET_KEYS
+            .iter()
+            .fold(Self::empty(), |policy, key| policy.key(*key));
+        SECRET_PATTERNS
+            .iter()
+            .fold(policy, |policy, pattern| policy.pattern(*pattern))
+    }
+}
+
+impl RedactionPolicy {
+    /// The built-in policy for common secret names.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A policy that masks nothing until keys, patterns or paths are added.
+    pub fn empty() -> Self {
+        Self {
+            keys: vec![],
+            patterns: vec![],
+            paths: vec![],
+            mask: Cow::Borrowed("REDACTED"),
+        }
+    }
+
+    /// Mask values under any key named `key`, compared case-insensitively.
+    pub fn key(mut self, key: impl Into<String>) -> Self {
+        self.keys.push(key.into());
+        self
+    }
+
+    /// Mask values under any key matching the glob `pattern`, in which `*`
+    /// matches any run of characters and `?` any single character, compared
+    /// case-insensitively.
+    pub fn pattern(mut self, pattern: impl Into<String>) -> Self {
+        self.patterns.push(pattern.into());
+        self
+    }
+
+    /// Mask the value at `path` and everything below it.  An empty bracket
+    /// matches every element of a list, so `users[][email]` masks each
+    /// user's email.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `path` is not a valid [`IndexPath`].
+    pub fn path<K>(mut self, path: K) -> Self
+    where
+        K: TryInto<IndexPath<'static>>,
+        K::Error: Debug,
+    {
+        let path = path.try_into().expect("invalid redacted path");
+        self.paths.push(path);
+        self
+    }
+
+    /// Replace masked strings with `mask` instead of `REDACTED`.
+    pub fn mask(mut self, mask: impl Into<Cow<'static, str>>) -> Self {
+        self.mask = mask.into();
+        self
+    }
+
+    fn matches(&self, path: &IndexPath<'_>) -> bool {
+        let key_matches = |key: &str| {
+            self.keys.iter().any(|k| k.eq_ignore_ascii_case(key))
+                || self.patterns.iter().any(|p| glob_match(p, key))
+        };
+
+        path.iter()
+            .any(|indexer| matches!(indexer, Indexer::String(key) if key_matches(key)))
+            || self.paths.iter().any(|prefix| {
+                prefix.len() <= path.len()
+                    && prefix.iter().zip(path.iter()).all(|(expected, actual)| {
+                        match (expected, actual) {
+                            (Indexer::Empty, Indexer::Number(_) | Indexer::Empty) => true,
+                            (expected, actual) => expected == actual,
+                        }
+                    })
+            })
+    }
+}
+
+impl<'a> QueryStrong<'a> {
+    /// A copy of this query with the values selected by `policy` masked, for
+    /// logging.  The copy implements [`Display`], [`Debug`] and, with the
+    /// `serde` feature, `Serialize`.
+    ///
+    /// Only values are masked, so the keys that were present stay visible.
+    /// Bare keys are left as they are.
+    pub fn redacted(&self, policy: &RedactionPolicy) -> Redacted<'a> {
+        let mut value = self.value.clone();
+        value.visit_mut(&mut Mask(policy));
+        Redacted(QueryStrong::from(value))
+    }
+}
+
+/// A query with sensitive values masked, returned by
+/// [`QueryStrong::redacted`].
+#[derive(Clone, PartialEq, Eq)]
+pub struct Redacted<'a>(QueryStrong<'a>);
+
+impl Display for Redacted<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl Debug for Redacted<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Debug::fmt(&self.0, f)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Redacted<'_> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+struct Mask<'p>(&'p RedactionPolicy);
+
+impl<'a> VisitMut<'a> for Mask<'_> {
+    fn visit_value_mut(&mut self, path: &mut IndexPath<'a>, value: &mut Value<'a>) {
+        if self.0.matches(path) {
+            let mask = &self.0.mask;
+            value.map_strings(|_| Cow::Owned(mask.to_string()));
+        } else {
+            walk_value_mut(self, path, value);
+        }
+    }
+}
+
+// Glob matching with `*` and `?`, ignoring ASCII case.  Backtracks only to
+// the most recent `*`, so it runs in O(pattern × text) time.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut star = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c.eq_ignore_ascii_case(&text[t]) => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    star = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::glob_match;
+
+    #[test]
+    fn glob() {
+        assert!(glob_match("*token*", "access_TOKEN"));
+        assert!(glob_match("*token*", "token"));
+        assert!(glob_match("a?c", "abc"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("a*b*c", "aXXbYYbc"));
+        assert!(!glob_match("a*b", "aXXbc"));
+        assert!(!glob_match("a?c", "ac"));
+    }
+}
@@ -0,0 +1,81 @@
+use crate::QueryStrong;
+use core::cell::Cell;
+use reqwest::{RequestBuilder, header::CONTENT_TYPE};
+use serde::{Serialize, Serializer};
+use std::string::ToString;
+
+/// Extension methods for [`reqwest::RequestBuilder`].  Requires the
+/// `reqwest` feature.
+///
+/// ```
+/// use querystrong::{QueryStrong, RequestBuilderExt};
+///
+/// let qs = QueryStrong::parse("filter[status][]=open&page=2");
+/// let request = reqwest::Client::new()
+///     .get("https://example.com/issues?sort=desc")
+///     .query_strong(&qs)
+///     .build()
+///     .unwrap();
+/// assert_eq!(
+///     request.url().as_str(),
+///     "https://example.com/issues?sort=desc&filter[status][]=open&page=2"
+/// );
+/// ```
+pub trait RequestBuilderExt {
+    /// Append `querystrong` serialized to the query of the request URL,
+    /// after any query already present.  Unlike
+    /// [`RequestBuilder::query`], brackets are written as they are, so
+    /// nested values keep their structure.
+    ///
+    /// If the builder has already failed, it is returned unchanged, so
+    /// sending it reports the original error.  The body is left as it is,
+    /// streaming or not.
+    fn query_strong(self, querystrong: &QueryStrong<'_>) -> Self;
+
+    /// Set the request body to `querystrong` serialized, with a
+    /// `Content-Type` of `application/x-www-form-urlencoded`.
+    fn form_strong(self, querystrong: &QueryStrong<'_>) -> Self;
+}
+
+impl RequestBuilderExt for RequestBuilder {
+    fn query_strong(self, querystrong: &QueryStrong<'_>) -> Self {
+        if querystrong.is_empty() {
+            return self;
+        }
+
+        // Splitting a failed builder would lose its error, since reqwest
+        // cannot rebuild a builder around one.  `query` only serializes its
+        // argument for a builder that has not failed, so an empty query tells
+        // the two apart without touching the body.
+        let unfailed = Cell::new(false);
+        let builder = self.query(&Unfailed(&unfailed));
+        if !unfailed.get() {
+            return builder;
+        }
+
+        let (client, request) = builder.build_split();
+        let mut request = request.expect("the builder has not failed");
+        let url = request.url_mut();
+        let query = match url.query() {
+            Some(query) if !query.is_empty() => format!("{query}&{querystrong}"),
+            _ => querystrong.to_string(),
+        };
+        url.set_query(Some(&query));
+        RequestBuilder::from_parts(client, request)
+    }
+
+    fn form_strong(self, querystrong: &QueryStrong<'_>) -> Self {
+        self.header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(querystrong.to_string())
+    }
+}
+
+// Serializes as no parameters at all, recording that it was asked to.
+struct Unfailed<'a>(&'a Cell<bool>);
+
+impl Serialize for Unfailed<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.set(true);
+        serializer.serialize_unit()
+    }
+}
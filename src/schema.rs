@@ -0,0 +1,439 @@
+use crate::{IndexPath, Indexer, Value};
+use alloc::{borrow::Cow, boxed::Box, collections::BTreeSet, string::String, vec::Vec};
+use core::{
+    convert::TryInto,
+    fmt::{self, Debug, Display, Formatter},
+};
+
+/// A declarative description of the paths a query is expected to contain.
+///
+/// A schema is built once from [`Field`]s and then checked against any number
+/// of values with [`validate`](Schema::validate), which reports every
+/// violation rather than stopping at the first.
+///
+/// ```
+/// use querystrong::{Field, QueryStrong, Schema};
+///
+/// let schema = Schema::new()
+///     .field("user[name]", Field::string().required().min(1))
+///     .field("user[age]", Field::integer().min(0).max(150))
+///     .field("sort", Field::one_of(["asc", "desc"]).default("asc"))
+///     .field("tags", Field::list_of(Field::string()).max(3));
+///
+/// assert!(schema.validate(&QueryStrong::parse("user[name]=jacob")).is_ok());
+///
+/// let violations = schema
+///     .validate(&QueryStrong::parse("user[age]=-1&sort=up&tags[]=a&tags[]=b&tags[]=c&tags[]=d"))
+///     .unwrap_err();
+/// let codes: Vec<_> = violations
+///     .iter()
+///     .map(|v| (v.path().to_string(), v.kind().code()))
+///     .collect();
+/// assert_eq!(
+///     codes,
+///     [
+///         ("user[name]".into(), "required"),
+///         ("user[age]".into(), "min"),
+///         ("sort".into(), "enum"),
+///         ("tags".into(), "max"),
+///     ]
+/// );
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Schema {
+    fields: Vec<(IndexPath<'static>, Field)>,
+}
+
+impl Schema {
+    /// Creates an empty schema, which every value satisfies.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a [`Field`] expected at `path`.
+    ///
+    /// Fields are checked in the order they were added.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `path` is not a valid [`IndexPath`]; schemas are expected to
+    /// be written by hand, so this is a programming error.
+    pub fn field<K>(mut self, path: K, field: Field) -> Self
+    where
+        K: TryInto<IndexPath<'static>>,
+        K::Error: Debug,
+    {
+        let path = path.try_into().expect("invalid schema path");
+        self.fields.push((path, field));
+        self
+    }
+
+    /// Check `value` against every field, returning all violations at once.
+    pub fn validate(&self, value: &Value<'_>) -> Result<(), Violations> {
+        let mut violations = Violations::default();
+        for (path, field) in &self.fields {
+            match value.get(path.clone()) {
+                Some(found) => field.check(path, found, &mut violations),
+                None if field.required && field.default.is_none() => {
+                    violations.push(path.clone(), ViolationKind::Required)
+                }
+                None => {}
+            }
+        }
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+
+    /// Insert the [`default`](Field::default) of every field that is absent
+    /// from `value`.
+    ///
+    /// Defaults are inserted with [`Value::append`]; a default whose path
+    /// conflicts with the existing structure is skipped, and will be reported
+    /// by [`validate`](Schema::validate) instead.
+    pub fn apply_defaults<'a>(&self, value: &mut Value<'a>) {
+        for (path, field) in &self.fields {
+            if let Some(default) = &field.default
+                && value.get(path.clone()).is_none()
+            {
+                let _ = value.append(path.clone(), default.clone());
+            }
+        }
+    }
+}
+
+/// The expected shape of a single path in a [`Schema`].
+///
+/// Constructed with one of the type constructors and refined with the
+/// builder methods.  Fields are optional unless marked
+/// [`required`](Field::required).
+#[derive(Debug, Clone)]
+pub struct Field {
+    kind: FieldKind,
+    required: bool,
+    default: Option<Value<'static>>,
+    min: Option<i64>,
+    max: Option<i64>,
+    #[cfg(feature = "regex")]
+    pattern: Option<regex::Regex>,
+}
+
+#[derive(Debug, Clone)]
+enum FieldKind {
+    String,
+    Integer,
+    OneOf(BTreeSet<String>),
+    ListOf(Box<Field>),
+    MapOf(Box<Field>),
+}
+
+impl Field {
+    fn new(kind: FieldKind) -> Self {
+        Self {
+            kind,
+            required: false,
+            default: None,
+            min: None,
+            max: None,
+            #[cfg(feature = "regex")]
+            pattern: None,
+        }
+    }
+
+    /// A string value.  [`min`](Field::min) and [`max`](Field::max) bound its
+    /// length in characters.
+    pub fn string() -> Self {
+        Self::new(FieldKind::String)
+    }
+
+    /// A string that parses as an `i64`.  [`min`](Field::min) and
+    /// [`max`](Field::max) bound the parsed number.
+    pub fn integer() -> Self {
+        Self::new(FieldKind::Integer)
+    }
+
+    /// A string that is exactly one of `allowed`.
+    pub fn one_of<I, S>(allowed: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self::new(FieldKind::OneOf(
+            allowed.into_iter().map(Into::into).collect(),
+        ))
+    }
+
+    /// A list whose every element satisfies `element`.  [`min`](Field::min)
+    /// and [`max`](Field::max) bound the number of elements.
+    ///
+    /// A single string is accepted as a list of one, since `tags=a` and
+    /// `tags=a&tags=b` differ only in how many times the key was repeated.
+    pub fn list_of(element: Field) -> Self {
+        Self::new(FieldKind::ListOf(Box::new(element)))
+    }
+
+    /// A map whose every value satisfies `value`.  [`min`](Field::min) and
+    /// [`max`](Field::max) bound the number of entries.
+    pub fn map_of(value: Field) -> Self {
+        Self::new(FieldKind::MapOf(Box::new(value)))
+    }
+
+    /// Report [`ViolationKind::Required`] when the path is absent and there is
+    /// no [`default`](Field::default).
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    /// A string to use when the path is absent; see [`Schema::apply_defaults`].
+    ///
+    /// `default` is taken literally, not percent-decoded, so a default of
+    /// `"a+b"` stays `a+b`.
+    pub fn default(self, default: impl Into<Cow<'static, str>>) -> Self {
+        self.default_value(Value::String(default.into()))
+    }
+
+    /// A value of any shape to use when the path is absent; see
+    /// [`Schema::apply_defaults`].
+    ///
+    /// The value is used as given.  Note that converting a `&str` into a
+    /// [`Value`] percent-decodes it; build strings with [`Value::String`] to
+    /// keep them literal.
+    pub fn default_value(mut self, default: Value<'static>) -> Self {
+        self.default = Some(default);
+        self
+    }
+
+    /// The inclusive lower bound; what it bounds depends on the field type.
+    pub fn min(mut self, min: i64) -> Self {
+        self.min = Some(min);
+        self
+    }
+
+    /// The inclusive upper bound; what it bounds depends on the field type.
+    pub fn max(mut self, max: i64) -> Self {
+        self.max = Some(max);
+        self
+    }
+
+    /// Require string values to match `pattern`.  Use `^` and `$` anchors to
+    /// match the whole value.
+    #[cfg(feature = "regex")]
+    pub fn pattern(mut self, pattern: regex::Regex) -> Self {
+        self.pattern = Some(pattern);
+        self
+    }
+
+    fn check(&self, path: &IndexPath<'static>, value: &Value<'_>, violations: &mut Violations) {
+        let decoded;
+        let value = match value {
+            Value::Encoded(e) => {
+                decoded = Value::String(Cow::Borrowed(e.as_str()));
+                &decoded
+            }
+            value => value,
+        };
+
+        let size = match (&self.kind, value) {
+            (FieldKind::String, Value::String(s)) => s.chars().count() as i64,
+
+            (FieldKind::Integer, Value::String(s)) => match s.parse::<i64>() {
+                Ok(n) => n,
+                Err(_) => return violations.push(path.clone(), ViolationKind::Integer),
+            },
+
+            (FieldKind::OneOf(allowed), Value::String(s)) => {
+                if !allowed.contains(&**s) {
+                    violations.push(path.clone(), ViolationKind::Enum);
+                }
+                return;
+            }
+
+            (FieldKind::ListOf(element), Value::String(_)) => {
+                element.check(path, value, violations);
+                1
+            }
+
+            (FieldKind::ListOf(element), Value::List(l)) => {
+                for (n, v) in l.iter().enumerate() {
+                    element.check(&child(path, Indexer::Number(n)), v, violations);
+                }
+                l.len() as i64
+            }
+
+            (FieldKind::ListOf(element), Value::SparseList(m)) => {
+                for (&n, v) in m {
+                    element.check(&child(path, Indexer::Number(n)), v, violations);
+                }
+                m.len() as i64
+            }
+
+            (FieldKind::MapOf(field), Value::Map(m)) => {
+                for (k, v) in m {
+                    let key = Indexer::String(k.clone().into_owned().into());
+                    field.check(&child(path, key), v, violations);
+                }
+                m.len() as i64
+            }
+
+            _ => return violations.push(path.clone(), ViolationKind::Type),
+        };
+
+        if self.min.is_some_and(|min| size < min) {
+            violations.push(path.clone(), ViolationKind::Min);
+        } else if self.max.is_some_and(|max| size > max) {
+            violations.push(path.clone(), ViolationKind::Max);
+        }
+
+        #[cfg(feature = "regex")]
+        if let (Some(pattern), Value::String(s)) = (&self.pattern, value)
+            && !pattern.is_match(s)
+        {
+            violations.push(path.clone(), ViolationKind::Pattern);
+        }
+    }
+}
+
+fn child(path: &IndexPath<'static>, indexer: Indexer<'static>) -> IndexPath<'static> {
+    let mut path = path.clone();
+    path.push_back(indexer);
+    path
+}
+
+/// What was wrong with the value at a [`Violation`]'s path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ViolationKind {
+    /// A [`required`](Field::required) path was absent.
+    Required,
+    /// The value was the wrong shape, e.g. a map where a string was expected.
+    Type,
+    /// An [`integer`](Field::integer) field did not parse as an `i64`.
+    Integer,
+    /// A [`one_of`](Field::one_of) field had a value outside the allowed set.
+    Enum,
+    /// The value, length or count was below [`min`](Field::min).
+    Min,
+    /// The value, length or count was above [`max`](Field::max).
+    Max,
+    /// A string did not match its `pattern` (requires the `regex` feature).
+    Pattern,
+}
+
+impl ViolationKind {
+    /// A stable, machine-readable identifier, suitable for API error bodies.
+    pub fn code(self) -> &'static str {
+        match self {
+            ViolationKind::Required => "required",
+            ViolationKind::Type => "type",
+            ViolationKind::Integer => "integer",
+            ViolationKind::Enum => "enum",
+            ViolationKind::Min => "min",
+            ViolationKind::Max => "max",
+            ViolationKind::Pattern => "pattern",
+        }
+    }
+}
+
+impl Display for ViolationKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ViolationKind::Required => "is required",
+            ViolationKind::Type => "has the wrong type",
+            ViolationKind::Integer => "is not an integer",
+            ViolationKind::Enum => "is not one of the allowed values",
+            ViolationKind::Min => "is below the minimum",
+            ViolationKind::Max => "is above the maximum",
+            ViolationKind::Pattern => "does not match the pattern",
+        })
+    }
+}
+
+/// A single failed expectation reported by [`Schema::validate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    path: IndexPath<'static>,
+    kind: ViolationKind,
+}
+
+impl Violation {
+    /// The path of the offending value.  For list and map elements this
+    /// includes the element's index or key.
+    pub fn path(&self) -> &IndexPath<'static> {
+        &self.path
+    }
+
+    /// What was wrong with the value.
+    pub fn kind(&self) -> ViolationKind {
+        self.kind
+    }
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}` {}", self.path, self.kind)
+    }
+}
+
+/// Every [`Violation`] found by one call to [`Schema::validate`], in schema
+/// order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Violations(Vec<Violation>);
+
+impl Violations {
+    fn push(&mut self, path: IndexPath<'static>, kind: ViolationKind) {
+        self.0.push(Violation { path, kind });
+    }
+
+    /// The individual violations.
+    pub fn violations(&self) -> &[Violation] {
+        &self.0
+    }
+
+    /// Iterate over the individual violations.
+    pub fn iter(&self) -> core::slice::Iter<'_, Violation> {
+        self.0.iter()
+    }
+
+    /// The number of violations.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Always `false` for a `Violations` returned by [`Schema::validate`].
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl<'a> IntoIterator for &'a Violations {
+    type Item = &'a Violation;
+    type IntoIter = core::slice::Iter<'a, Violation>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl core::error::Error for Violations {}
+
+impl Display for Violations {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} schema {}:",
+            self.0.len(),
+            if self.0.len() == 1 {
+                "violation"
+            } else {
+                "violations"
+            },
+        )?;
+        for violation in &self.0 {
+            writeln!(f, "  - {violation}")?;
+        }
+        Ok(())
+    }
+}
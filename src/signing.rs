@@ -0,0 +1,210 @@
+use crate::{QueryStrong, Value, decode, split_pairs};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::{
+    borrow::Cow,
+    fmt::{self, Debug, Formatter, Write},
+    string::{String, ToString},
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+    vec::Vec,
+};
+use thiserror::Error;
+
+const EXPIRES: &str = "expires";
+const SIGNATURE: &str = "signature";
+
+/// Signs query strings with an expiry, and verifies them.  Requires the
+/// `signing` feature.
+///
+/// [`sign`](Signer::sign) sets an `expires` parameter (seconds since the Unix
+/// epoch) and a `signature` parameter, the lowercase hex HMAC-SHA256 of the
+/// query string as [`Display`](std::fmt::Display) writes it without
+/// `signature`.  Send the signed query as `to_string()` writes it.
+/// [`verify`](Signer::verify) recomputes the signature over the received
+/// query string exactly as written, less the `signature` pair, and compares
+/// it in constant time.  Adding, removing, changing or reordering any
+/// parameter, or writing a bare key `k` as `k=`, invalidates the link.
+///
+/// # What is signed
+///
+/// The signed form is the `Display` serialization, in the order `Display`
+/// writes it, not [`canonical_string`](QueryStrong::canonical_string).  The
+/// canonical form sorts keys and writes a bare `k` as `k=`, so a signature
+/// over it could not tell a reordered or respelled link from the original.
+/// A link is therefore only valid byte for byte as `sign` produced it, apart
+/// from where the `signature` pair sits: anything that re-encodes the query
+/// on the way, such as writing `[` as `%5B`, invalidates it.
+///
+/// ```
+/// use querystrong::{QueryStrong, SignatureError, Signer};
+/// use std::time::{Duration, UNIX_EPOCH};
+///
+/// let signer = Signer::new(b"secret key")
+///     .with_clock(|| UNIX_EPOCH + Duration::from_secs(1_700_000_000));
+///
+/// let mut qs = QueryStrong::parse("file=report.pdf&user=42");
+/// signer.sign(&mut qs, Duration::from_secs(3600)).unwrap();
+/// assert_eq!(qs["expires"], "1700003600");
+/// let link = qs.to_string();
+///
+/// assert_eq!(signer.verify(&link), Ok(()));
+///
+/// let tampered = link.replace("user=42", "user=43");
+/// assert_eq!(signer.verify(&tampered), Err(SignatureError::Invalid));
+///
+/// let later = signer.with_clock(|| UNIX_EPOCH + Duration::from_secs(1_700_003_600));
+/// assert_eq!(later.verify(&link), Err(SignatureError::Expired));
+/// ```
+#[derive(Clone)]
+pub struct Signer {
+    key: Vec<u8>,
+    clock: Arc<dyn Fn() -> SystemTime + Send + Sync>,
+}
+
+impl Debug for Signer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Signer").finish_non_exhaustive()
+    }
+}
+
+/// Why [`Signer::sign`] could not sign a query string, or [`Signer::verify`]
+/// rejected one.
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureError {
+    /// There is no `signature` or `expires` parameter, or one of them is not
+    /// a single string of the expected form.
+    #[error("missing or malformed signature parameters")]
+    Malformed,
+
+    /// The signature does not match the parameters.
+    #[error("invalid signature")]
+    Invalid,
+
+    /// The signature is valid, but its `expires` time has passed.
+    #[error("signature expired")]
+    Expired,
+
+    /// The expiry time passed to [`Signer::sign`] is too far in the future
+    /// to represent.
+    #[error("expiry time out of range")]
+    ExpiryOutOfRange,
+
+    /// The query string passed to [`Signer::sign`] is not a map at the top
+    /// level, so it cannot hold the signature parameters.
+    #[error("cannot sign a query string that is not a map")]
+    NotAMap,
+}
+
+impl Signer {
+    /// A signer using `key` as the HMAC key, and the system clock.
+    pub fn new(key: impl AsRef<[u8]>) -> Self {
+        Self {
+            key: key.as_ref().to_vec(),
+            clock: Arc::new(SystemTime::now),
+        }
+    }
+
+    /// Use `clock` instead of the system clock for the current time, such as
+    /// a fixed time in tests.
+    pub fn with_clock(mut self, clock: impl Fn() -> SystemTime + Send + Sync + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Set `expires` to `ttl` from now and `signature` to the signature of
+    /// the result as `Display` writes it, replacing any existing values.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SignatureError::NotAMap`] if `querystrong` is not a map at
+    /// the top level, and [`SignatureError::ExpiryOutOfRange`] if `ttl` from
+    /// now cannot be represented.
+    pub fn sign(
+        &self,
+        querystrong: &mut QueryStrong<'_>,
+        ttl: Duration,
+    ) -> Result<(), SignatureError> {
+        let expires = (self.clock)()
+            .checked_add(ttl)
+            .ok_or(SignatureError::ExpiryOutOfRange)?;
+        let expires = expires
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        querystrong.take(SIGNATURE);
+        querystrong.take(EXPIRES);
+        querystrong
+            .append(EXPIRES, Value::String(Cow::Owned(expires.to_string())))
+            .map_err(|_| SignatureError::NotAMap)?;
+        let mut mac = self.mac();
+        mac.update(querystrong.to_string().as_bytes());
+        let signature = mac.finalize().into_bytes();
+        let mut hex = String::with_capacity(signature.len() * 2);
+        for b in signature {
+            let _ = write!(hex, "{b:02x}");
+        }
+        querystrong
+            .append(SIGNATURE, Value::String(Cow::Owned(hex)))
+            .map_err(|_| SignatureError::NotAMap)
+    }
+
+    /// Check that the query string `query` was signed with this signer's key,
+    /// is unchanged since, and has not expired.
+    ///
+    /// `query` is the query string as received, without a leading `?`: the
+    /// order and spelling of its parameters are part of what was signed.  The
+    /// signature is checked before the expiry, so a tampered link is reported
+    /// as [`SignatureError::Invalid`] whether or not it has expired.
+    pub fn verify(&self, query: &str) -> Result<(), SignatureError> {
+        let querystrong = QueryStrong::parse(query);
+        let signature = querystrong
+            .get_str(SIGNATURE)
+            .and_then(decode_hex)
+            .ok_or(SignatureError::Malformed)?;
+        let expires: u64 = querystrong
+            .get_str(EXPIRES)
+            .and_then(|expires| expires.parse().ok())
+            .ok_or(SignatureError::Malformed)?;
+
+        let mut mac = self.mac();
+        let unsigned = split_pairs(query).filter(|(key, _)| decode(*key) != SIGNATURE);
+        for (i, (key, value)) in unsigned.enumerate() {
+            if i > 0 {
+                mac.update(b"&");
+            }
+            mac.update(key.as_bytes());
+            if let Some(value) = value {
+                mac.update(b"=");
+                mac.update(value.as_bytes());
+            }
+        }
+        mac.verify_slice(&signature)
+            .map_err(|_| SignatureError::Invalid)?;
+
+        let now = (self.clock)()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        if now >= expires {
+            Err(SignatureError::Expired)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn mac(&self) -> Hmac<Sha256> {
+        Hmac::<Sha256>::new_from_slice(&self.key).expect("HMAC accepts keys of any length")
+    }
+}
+
+fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    s.as_bytes()
+        .chunks(2)
+        .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).ok()?, 16).ok())
+        .collect()
+}
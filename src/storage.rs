@@ -0,0 +1,104 @@
+use alloc::{borrow::Cow, boxed::Box, rc::Rc, string::String, sync::Arc};
+use core::{borrow::Borrow, ops::Deref};
+
+/// How a [`Value`](crate::Value) or [`Indexer`](crate::Indexer) stores its
+/// strings: map keys, string leaves and string key segments.
+///
+/// Parsing always produces the default, `Cow<'a, str>`, which borrows from the
+/// input wherever no decoding was needed.  For values that outlive the input,
+/// such as long-lived caches of parsed queries, convert to a cheaper
+/// representation with [`Value::into_storage`](crate::Value::into_storage):
+/// `Arc<str>` clones without copying, and a small-string or interned-symbol
+/// type can be plugged in by implementing this trait.
+///
+/// ```
+/// use querystrong::{QueryStrong, Value};
+/// use std::sync::Arc;
+///
+/// let input = String::from("user[name]=jacob&tags[]=a&tags[]=b");
+/// let qs = QueryStrong::parse(&input);
+/// let cached: Value<'static, Arc<str>> = qs.into_value().into_storage();
+/// drop(input);
+///
+/// assert_eq!(cached.get_str("user[name]"), Some("jacob"));
+/// assert_eq!(cached["tags"][1], "b");
+/// ```
+///
+/// # Scope
+///
+/// Only the core [`Value`](crate::Value) API is generic over the storage:
+/// building, reading, `take`, comparing and iterating over pairs.  The rest
+/// of the crate works on the default `Cow<'a, str>` storage only.  That
+/// covers parsing, [`QueryStrong`](crate::QueryStrong), the
+/// [`Visit`](crate::Visit) and [`VisitMut`](crate::VisitMut) traversals and
+/// the methods built on them, flattening, permitting, canonical forms,
+/// redaction, [`Schema`](crate::Schema) validation and conversion to and
+/// from JSON.  [`Error`](crate::Error)s report values in the default
+/// representation too.  To use any of these on a value with another
+/// storage, convert it back first with
+/// [`into_default_storage`](crate::Value::into_default_storage), which copies
+/// every string.
+///
+/// [`Indexer`](crate::Indexer) and [`IndexPath`](crate::IndexPath) always use
+/// `Cow`.  Their segments are parsed for each lookup and never stored in a
+/// value: looking up `"user[name]"` in a `Value<'_, Arc<str>>` borrows each
+/// segment from the key and compares it with the stored keys through
+/// `Borrow<str>`, so no string is allocated unless a segment needs
+/// percent-decoding.
+pub trait StringStorage<'a>: Clone + Ord + Deref<Target = str> + Borrow<str> {
+    /// Store `s`.
+    fn from_cow(s: Cow<'a, str>) -> Self;
+
+    /// Give up the stored string.
+    fn into_cow(self) -> Cow<'a, str>;
+}
+
+impl<'a> StringStorage<'a> for Cow<'a, str> {
+    fn from_cow(s: Cow<'a, str>) -> Self {
+        s
+    }
+
+    fn into_cow(self) -> Cow<'a, str> {
+        self
+    }
+}
+
+impl<'a> StringStorage<'a> for String {
+    fn from_cow(s: Cow<'a, str>) -> Self {
+        s.into_owned()
+    }
+
+    fn into_cow(self) -> Cow<'a, str> {
+        Cow::Owned(self)
+    }
+}
+
+impl<'a> StringStorage<'a> for Box<str> {
+    fn from_cow(s: Cow<'a, str>) -> Self {
+        s.into()
+    }
+
+    fn into_cow(self) -> Cow<'a, str> {
+        Cow::Owned(self.into())
+    }
+}
+
+impl<'a> StringStorage<'a> for Arc<str> {
+    fn from_cow(s: Cow<'a, str>) -> Self {
+        s.into()
+    }
+
+    fn into_cow(self) -> Cow<'a, str> {
+        Cow::Owned(String::from(&*self))
+    }
+}
+
+impl<'a> StringStorage<'a> for Rc<str> {
+    fn from_cow(s: Cow<'a, str>) -> Self {
+        s.into()
+    }
+
+    fn into_cow(self) -> Cow<'a, str> {
+        Cow::Owned(String::from(&*self))
+    }
+}
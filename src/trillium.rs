@@ -0,0 +1,155 @@
+use crate::QueryStrong;
+use std::{boxed::Box, string::ToString};
+use trillium::{Conn, Handler, KnownHeaderName, Status, async_trait};
+
+/// What [`QueryStrongHandler`] does when the query string or form body has
+/// parse errors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OnParseError {
+    /// Store the parsed value, discarding the errors.
+    Ignore,
+
+    /// Respond with `400 Bad Request`, listing the errors in the body, and
+    /// halt.
+    Halt,
+
+    /// Store the parsed value with its errors, available through
+    /// [`QueryStrong::errors`].
+    #[default]
+    Store,
+}
+
+/// A trillium handler that parses the request query string, and optionally
+/// an `application/x-www-form-urlencoded` body, into conn state.  Read them
+/// with [`ConnExt`].  Requires the `trillium` feature.
+///
+/// ```
+/// use querystrong::{ConnExt, OnParseError, QueryStrongHandler};
+/// use trillium::Conn;
+///
+/// let app = (
+///     QueryStrongHandler::new().on_parse_error(OnParseError::Halt),
+///     |conn: Conn| async move {
+///         let page = conn
+///             .query_strong()
+///             .and_then(|qs| qs.get_str("page[number]"))
+///             .unwrap_or("1")
+///             .to_string();
+///         conn.ok(page)
+///     },
+/// );
+/// # use trillium_testing::prelude::*;
+/// # assert_ok!(get("/?page[number]=3").on(&app), "3");
+/// # assert_status!(get("/?page=1&page[number]=3").on(&app), 400);
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct QueryStrongHandler {
+    on_parse_error: OnParseError,
+    form_body: bool,
+}
+
+impl QueryStrongHandler {
+    /// A handler that parses the query string only, and stores parse errors.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Choose what to do when there are parse errors.
+    pub fn on_parse_error(mut self, on_parse_error: OnParseError) -> Self {
+        self.on_parse_error = on_parse_error;
+        self
+    }
+
+    /// Also parse the request body if it is
+    /// `application/x-www-form-urlencoded`, for [`ConnExt::form_strong`].
+    pub fn with_form_body(mut self) -> Self {
+        self.form_body = true;
+        self
+    }
+
+    // Applies the parse-error policy, returning the value to store, or the
+    // halted conn.
+    fn check(
+        &self,
+        conn: Conn,
+        mut qs: QueryStrong<'static>,
+    ) -> (Conn, Option<QueryStrong<'static>>) {
+        match (qs.errors(), self.on_parse_error) {
+            (Some(errors), OnParseError::Halt) => {
+                let body = errors.to_string();
+                (
+                    conn.with_status(Status::BadRequest).with_body(body).halt(),
+                    None,
+                )
+            }
+            (Some(_), OnParseError::Ignore) => {
+                qs.errors = None;
+                (conn, Some(qs))
+            }
+            _ => (conn, Some(qs)),
+        }
+    }
+}
+
+struct QueryState(QueryStrong<'static>);
+struct FormState(QueryStrong<'static>);
+
+#[async_trait]
+impl Handler for QueryStrongHandler {
+    async fn run(&self, conn: Conn) -> Conn {
+        let qs = QueryStrong::parse(conn.querystring()).into_owned();
+        let (mut conn, qs) = self.check(conn, qs);
+        let Some(qs) = qs else { return conn };
+        conn.insert_state(QueryState(qs));
+
+        let is_form = conn
+            .request_headers()
+            .get_str(KnownHeaderName::ContentType)
+            .and_then(|content_type| content_type.split(';').next())
+            .is_some_and(|mime| {
+                mime.trim()
+                    .eq_ignore_ascii_case("application/x-www-form-urlencoded")
+            });
+        if !self.form_body || !is_form {
+            return conn;
+        }
+
+        let body = match conn.request_body_string().await {
+            Ok(body) => body,
+            Err(error) => {
+                return conn
+                    .with_status(Status::BadRequest)
+                    .with_body(error.to_string())
+                    .halt();
+            }
+        };
+        let (mut conn, form) = self.check(conn, QueryStrong::parse(&body).into_owned());
+        if let Some(form) = form {
+            conn.insert_state(FormState(form));
+        }
+        conn
+    }
+}
+
+/// Accessors for the values parsed by [`QueryStrongHandler`].  Requires the
+/// `trillium` feature.
+pub trait ConnExt {
+    /// The parsed query string, or `None` if [`QueryStrongHandler`] has not
+    /// run on this conn.
+    fn query_strong(&self) -> Option<&QueryStrong<'static>>;
+
+    /// The parsed form body, or `None` if [`QueryStrongHandler`] has not run
+    /// on this conn with [`with_form_body`](QueryStrongHandler::with_form_body),
+    /// or the request body is not `application/x-www-form-urlencoded`.
+    fn form_strong(&self) -> Option<&QueryStrong<'static>>;
+}
+
+impl ConnExt for Conn {
+    fn query_strong(&self) -> Option<&QueryStrong<'static>> {
+        self.state::<QueryState>().map(|state| &state.0)
+    }
+
+    fn form_strong(&self) -> Option<&QueryStrong<'static>> {
+        self.state::<FormState>().map(|state| &state.0)
+    }
+}
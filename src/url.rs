@@ -0,0 +1,103 @@
+use crate::QueryStrong;
+use std::string::ToString;
+use url::{Url, form_urlencoded::Serializer};
+
+impl<'a> QueryStrong<'a> {
+    /// Parse the query component of `url`, borrowing from it.  A URL without
+    /// a query gives an empty `QueryStrong`.  Requires the `url` feature.
+    ///
+    /// ```
+    /// use querystrong::QueryStrong;
+    /// use url::Url;
+    ///
+    /// let url = Url::parse("https://example.com/search?q=rust&page[size]=10").unwrap();
+    /// let qs = QueryStrong::from_url(&url);
+    /// assert_eq!(qs["page[size]"], "10");
+    /// ```
+    pub fn from_url(url: &'a Url) -> Self {
+        QueryStrong::parse(url.query().unwrap_or_default())
+    }
+}
+
+/// Extension methods for [`url::Url`].  Requires the `url` feature.
+pub trait UrlExt {
+    /// Parse the query component, borrowing from the URL.  The same as
+    /// [`QueryStrong::from_url`].
+    fn query_strong(&self) -> QueryStrong<'_>;
+
+    /// Replace the query component with `querystrong` serialized, or remove
+    /// it if `querystrong` is empty.
+    ///
+    /// ```
+    /// use querystrong::{QueryStrong, UrlExt};
+    /// use url::Url;
+    ///
+    /// let mut url = Url::parse("https://example.com/search?q=rust").unwrap();
+    /// let mut qs = url.query_strong().into_owned();
+    /// qs.append("filter[tags][]", "web").unwrap();
+    /// url.set_query_strong(&qs);
+    /// assert_eq!(
+    ///     url.as_str(),
+    ///     "https://example.com/search?filter[tags][]=web&q=rust"
+    /// );
+    ///
+    /// url.set_query_strong(&QueryStrong::new());
+    /// assert_eq!(url.as_str(), "https://example.com/search");
+    /// ```
+    fn set_query_strong(&mut self, querystrong: &QueryStrong<'_>);
+}
+
+impl UrlExt for Url {
+    fn query_strong(&self) -> QueryStrong<'_> {
+        QueryStrong::from_url(self)
+    }
+
+    fn set_query_strong(&mut self, querystrong: &QueryStrong<'_>) {
+        if querystrong.is_empty() {
+            self.set_query(None);
+        } else {
+            self.set_query(Some(&querystrong.to_string()));
+        }
+    }
+}
+
+/// Extension methods for [`url::form_urlencoded::Serializer`], including
+/// the one returned by [`Url::query_pairs_mut`].  Requires the `url`
+/// feature.
+pub trait FormSerializerExt {
+    /// Append every parameter of `querystrong`, with keys in bracket
+    /// notation (`user[name]`) and values decoded, for the serializer to
+    /// encode.  Bare keys are appended with
+    /// [`append_key_only`](Serializer::append_key_only).
+    ///
+    /// The serializer escapes brackets, so the result reads
+    /// `user%5Bname%5D=jacob`.  Most servers decode that back to nested
+    /// parameters, but [`QueryStrong::parse`] treats an escaped bracket as
+    /// part of the key; use [`UrlExt::set_query_strong`] to keep the
+    /// structure for this crate.
+    ///
+    /// ```
+    /// use querystrong::{FormSerializerExt, QueryStrong};
+    /// use url::form_urlencoded::Serializer;
+    ///
+    /// let qs = QueryStrong::parse("user[name]=jacob+r&flag");
+    /// let body = Serializer::new(String::new())
+    ///     .append_pair("v", "1")
+    ///     .append_query_strong(&qs)
+    ///     .finish();
+    /// assert_eq!(body, "v=1&flag&user%5Bname%5D=jacob+r");
+    /// ```
+    fn append_query_strong(&mut self, querystrong: &QueryStrong<'_>) -> &mut Self;
+}
+
+impl<'a, T: url::form_urlencoded::Target> FormSerializerExt for Serializer<'a, T> {
+    fn append_query_strong(&mut self, querystrong: &QueryStrong<'_>) -> &mut Self {
+        for (key, value) in querystrong.decoded_pairs() {
+            match value {
+                Some(value) => self.append_pair(&key, &value),
+                None => self.append_key_only(&key),
+            };
+        }
+        self
+    }
+}
@@ -1,8 +1,12 @@
-use crate::{Error, IndexPath, Indexer, Result};
-use std::borrow::Cow;
-use std::convert::TryInto;
-use std::mem;
-use std::{collections::BTreeMap, fmt::Debug, iter, ops::Index};
+use crate::{EncodedStr, Error, IndexPath, Indexer, Result, StringStorage};
+use alloc::{borrow::Cow, boxed::Box, collections::BTreeMap, string::String, vec::Vec};
+use core::convert::TryInto;
+use core::mem;
+use core::{
+    fmt::Debug,
+    iter,
+    ops::{Deref, Index},
+};
 
 /// A node in the parsed query-string value tree.
 ///
@@ -11,6 +15,9 @@ use std::{collections::BTreeMap, fmt::Debug, iter, ops::Index};
 /// pointing into the original input.  Call [`Value::into_owned`] to obtain a
 /// `'static` value.
 ///
+/// `S` is how map keys and string leaves are stored.  It defaults to
+/// `Cow<'a, str>`; see [`StringStorage`] for the alternatives.
+///
 /// # Variant summary
 ///
 /// | Variant        | Produced by                    | Serializes as   |
@@ -19,15 +26,16 @@ use std::{collections::BTreeMap, fmt::Debug, iter, ops::Index};
 /// | `List`         | `[]` appends                   | `k[]=v`         |
 /// | `SparseList`   | explicit `[n]` numeric indices | `k[n]=v`        |
 /// | `String`       | plain value (`k=v`)            | `k=v`           |
+/// | `Encoded`      | [`QueryStrong::parse_lazy`](crate::QueryStrong::parse_lazy) | `k=v` |
 /// | `Empty`        | key with no value (`k`)        | `k`             |
-#[derive(Clone, PartialEq, Eq, Default)]
-pub enum Value<'a> {
+#[derive(Clone, Default)]
+pub enum Value<'a, S = Cow<'a, str>> {
     /// A string-keyed map, produced by bracket-notation keys (`a[b]=v`).
-    Map(BTreeMap<Cow<'a, str>, Value<'a>>),
+    Map(BTreeMap<S, Value<'a, S>>),
     /// A dense, contiguous list produced by empty-bracket appends (`a[]=v`).
     ///
     /// Serializes with `[]` notation.
-    List(Vec<Value<'a>>),
+    List(Vec<Value<'a, S>>),
     /// A sparse list produced by explicit numeric indices (`a[5]=v`).
     ///
     /// Backed by a `BTreeMap<usize, Value>` so a large index like `a[999999]=v`
@@ -36,29 +44,57 @@ pub enum Value<'a> {
     /// slots beyond `max_key` return `None` from [`Value::get`].
     ///
     /// Serializes with `[n]` notation, preserving the original indices.
-    SparseList(BTreeMap<usize, Value<'a>>),
+    SparseList(BTreeMap<usize, Value<'a, S>>),
     /// A string value, possibly borrowed from the input when no decoding was needed.
-    String(Cow<'a, str>),
+    String(S),
+    /// A string value whose percent-decoding has been deferred, holding the raw
+    /// slice of the input.  Produced only by
+    /// [`QueryStrong::parse_lazy`](crate::QueryStrong::parse_lazy), for values
+    /// that contain `%` or `+`.
+    ///
+    /// It behaves as the [`String`](Value::String) it decodes to: it compares
+    /// equal to it, displays, serializes and converts the same way, and
+    /// [`as_str`](Value::as_str) and [`get_str`](Value::get_str) read it.  The
+    /// value is decoded on first access and the result cached.
+    Encoded(EncodedStr<'a>),
     /// The absence of a value, produced by a key with no `=` (e.g. bare `k`).
     #[default]
     Empty,
 }
 
-impl Debug for Value<'_> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl<S: Deref<Target = str>> Debug for Value<'_, S> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match &self {
-            Value::Map(m) => f.debug_map().entries(m).finish(),
+            Value::Map(m) => f
+                .debug_map()
+                .entries(m.iter().map(|(k, v)| (&**k, v)))
+                .finish(),
             Value::List(l) => f.debug_list().entries(l).finish(),
             Value::SparseList(m) => f.debug_map().entries(m).finish(),
-            Value::String(s) => Debug::fmt(s, f),
+            Value::String(s) => Debug::fmt(&**s, f),
+            Value::Encoded(e) => Debug::fmt(e.as_str(), f),
             _ => f.write_str("()"),
         }
     }
 }
 
 impl<'a> Value<'a> {
+    /// The deepest key path [`append`](Value::append) accepts, counting each
+    /// segment: `a[b][c]` is three levels deep.
+    ///
+    /// [`append`](Value::append), [`get`](Value::get) and
+    /// [`take`](Value::take) walk a path iteratively, but
+    /// [`into_owned`](Value::into_owned), iterating, serializing, formatting,
+    /// cloning, comparing and dropping a value recurse once per level.  This
+    /// bound is what keeps those from overflowing the stack on a hostile query
+    /// string such as `a[a][a][a]…`.  Deeper keys are rejected with
+    /// [`Error::TooDeep`], and [`QueryStrong::parse`](crate::QueryStrong::parse)
+    /// records that error and skips the pair.
+    pub const MAX_DEPTH: usize = 100;
+
     /// Deep-clone this value into a `Value<'static>`, converting any
-    /// `Cow::Borrowed` strings to owned `String`s.
+    /// `Cow::Borrowed` strings to owned `String`s and decoding any
+    /// [`Encoded`](Value::Encoded) leaves.
     pub fn into_owned(self) -> Value<'static> {
         match self {
             Value::Map(btree_map) => Value::Map(
@@ -77,6 +113,7 @@ impl<'a> Value<'a> {
                     .collect(),
             ),
             Value::String(cow) => Value::String(cow.into_owned().into()),
+            Value::Encoded(e) => Value::String(e.into_cow().into_owned().into()),
             Value::Empty => Value::Empty,
         }
     }
@@ -95,15 +132,73 @@ impl<'a> Value<'a> {
     pub fn new_sparse_list() -> Self {
         Self::SparseList(BTreeMap::new())
     }
+}
+
+impl<'a, S: StringStorage<'a>> Value<'a, S> {
+    /// Convert to an owned [`StringStorage`] such as `Arc<str>`, decoding any
+    /// [`Encoded`](Value::Encoded) leaves.  The result no longer borrows from
+    /// the input.
+    pub fn into_storage<'b, T: for<'x> StringStorage<'x>>(self) -> Value<'b, T> {
+        match self {
+            Value::Map(m) => Value::Map(
+                m.into_iter()
+                    .map(|(k, v)| (T::from_cow(k.into_cow()), v.into_storage()))
+                    .collect(),
+            ),
+            Value::List(l) => Value::List(l.into_iter().map(Value::into_storage).collect()),
+            Value::SparseList(m) => {
+                Value::SparseList(m.into_iter().map(|(k, v)| (k, v.into_storage())).collect())
+            }
+            Value::String(s) => Value::String(T::from_cow(s.into_cow())),
+            Value::Encoded(e) => Value::String(T::from_cow(e.into_cow())),
+            Value::Empty => Value::Empty,
+        }
+    }
+
+    /// Convert back to the default `Cow<'a, str>` storage, which the parts of
+    /// the crate beyond the core `Value` API require; see
+    /// [`StringStorage`](crate::StringStorage#scope).
+    ///
+    /// ```
+    /// use querystrong::{Field, QueryStrong, Schema, Value};
+    /// use std::sync::Arc;
+    ///
+    /// let cached: Value<'static, Arc<str>> =
+    ///     QueryStrong::parse("page=2").into_value().into_storage();
+    /// let schema = Schema::new().field("page", Field::integer());
+    /// assert!(schema.validate(&cached.clone().into_default_storage()).is_ok());
+    /// ```
+    pub fn into_default_storage(self) -> Value<'a> {
+        self.map_storage()
+    }
+
+    // Convert to another storage for the same input, keeping `Encoded` leaves.
+    fn map_storage<T: StringStorage<'a>>(self) -> Value<'a, T> {
+        match self {
+            Value::Map(m) => Value::Map(
+                m.into_iter()
+                    .map(|(k, v)| (T::from_cow(k.into_cow()), v.map_storage()))
+                    .collect(),
+            ),
+            Value::List(l) => Value::List(l.into_iter().map(Value::map_storage).collect()),
+            Value::SparseList(m) => {
+                Value::SparseList(m.into_iter().map(|(k, v)| (k, v.map_storage())).collect())
+            }
+            Value::String(s) => Value::String(T::from_cow(s.into_cow())),
+            Value::Encoded(e) => Value::Encoded(e),
+            Value::Empty => Value::Empty,
+        }
+    }
 
     /// Returns `true` if this value is a [`Map`](Value::Map).
     pub fn is_map(&self) -> bool {
         matches!(self, &Self::Map(_))
     }
 
-    /// Returns `true` if this value is a [`String`](Value::String).
+    /// Returns `true` if this value is a [`String`](Value::String) or an
+    /// [`Encoded`](Value::Encoded) string.
     pub fn is_string(&self) -> bool {
-        matches!(self, &Self::String(_))
+        matches!(self, &Self::String(_) | &Self::Encoded(_))
     }
 
     /// Returns true for both dense `List` and `SparseList` variants.
@@ -131,7 +226,7 @@ impl<'a> Value<'a> {
 
     /// Returns a reference to the inner map if this is a [`Map`](Value::Map),
     /// otherwise `None`.
-    pub fn as_map(&self) -> Option<&BTreeMap<Cow<'a, str>, Value<'a>>> {
+    pub fn as_map(&self) -> Option<&BTreeMap<S, Value<'a, S>>> {
         match self {
             Self::Map(m) => Some(m),
             _ => None,
@@ -140,21 +235,33 @@ impl<'a> Value<'a> {
 
     /// Returns a reference to the inner map if this is a [`SparseList`](Value::SparseList),
     /// otherwise `None`.
-    pub fn as_sparse_list(&self) -> Option<&BTreeMap<usize, Value<'a>>> {
+    pub fn as_sparse_list(&self) -> Option<&BTreeMap<usize, Value<'a, S>>> {
         match self {
             Self::SparseList(m) => Some(m),
             _ => None,
         }
     }
 
-    /// Returns the inner string slice if this is a [`String`](Value::String),
-    /// otherwise `None`.
+    /// Returns the string if this is a [`String`](Value::String) or an
+    /// [`Encoded`](Value::Encoded) string, otherwise `None`.
     ///
     /// When the original input was not percent-encoded, the returned `&str`
-    /// points directly into the source `&'a str` without copying.
-    pub fn as_str<'b: 'a>(&'b self) -> Option<&'a str> {
+    /// points directly into the source `&'a str` without copying.  An
+    /// `Encoded` string, from
+    /// [`QueryStrong::parse_lazy`](crate::QueryStrong::parse_lazy), is decoded
+    /// on the first call and the result cached.
+    ///
+    /// ```
+    /// use querystrong::QueryStrong;
+    ///
+    /// let qs = QueryStrong::parse_lazy("q=rust+lang&page=2");
+    /// assert_eq!(qs["q"].as_str(), Some("rust lang"));
+    /// assert_eq!(qs["page"].as_str(), Some("2"));
+    /// ```
+    pub fn as_str(&self) -> Option<&str> {
         match self {
-            Self::String(s) => Some(&**s),
+            Self::String(s) => Some(s),
+            Self::Encoded(e) => Some(e.as_str()),
             _ => None,
         }
     }
@@ -168,6 +275,7 @@ impl<'a> Value<'a> {
             Value::List(l) => l.is_empty() || l.iter().all(Value::is_empty),
             Value::SparseList(m) => m.is_empty() || m.values().all(Value::is_empty),
             Value::String(s) => s.is_empty(),
+            Value::Encoded(e) => e.is_empty(),
             Value::Empty => true,
         }
     }
@@ -180,6 +288,7 @@ impl<'a> Value<'a> {
             Value::List(l) => l.len(),
             Value::SparseList(m) => m.len(),
             Value::String(s) => s.len(),
+            Value::Encoded(e) => e.len(),
             Value::Empty => 0,
         }
     }
@@ -193,7 +302,7 @@ impl<'a> Value<'a> {
     ///
     /// Returns an error when the existing tree structure is incompatible with
     /// the requested path (e.g. appending `a[b]=2` when `a` is already a
-    /// string).
+    /// string), or when the path is deeper than [`MAX_DEPTH`](Value::MAX_DEPTH).
     pub fn append<'b: 'a, K, V>(&mut self, key: K, value: V) -> Result<'a, ()>
     where
         K: TryInto<IndexPath<'b>>,
@@ -201,14 +310,36 @@ impl<'a> Value<'a> {
         K::Error: Into<Error<'a>>,
         V::Error: Into<Error<'a>>,
     {
-        let mut index_path = key.try_into().map_err(Into::into)?;
-        let value = value.try_into().map_err(Into::into)?;
-        let (self_value, error) =
-            mem::take(self).inner_append(index_path.pop_front(), index_path, value);
-        *self = self_value;
-        match error {
-            Some(error) => Err(error),
-            None => Ok(()),
+        let mut index_path: IndexPath<'a> = key.try_into().map_err(Into::into)?;
+        if index_path.len() > <Value>::MAX_DEPTH {
+            return Err(Error::TooDeep(index_path.len()));
+        }
+        let value: Value<'a> = value.try_into().map_err(Into::into)?;
+        self.append_path(&mut index_path, value.map_storage())
+    }
+
+    // `append` with the path already parsed and within `MAX_DEPTH`.  Takes
+    // segments off the front of `index_path`, leaving its buffer for the
+    // caller to clear and reuse.
+    pub(crate) fn append_path(
+        &mut self,
+        index_path: &mut IndexPath<'a>,
+        mut value: Value<'a, S>,
+    ) -> Result<'a, ()> {
+        // Descends one level per iteration rather than recursing, so the stack
+        // stays flat however deep the path is.
+        let mut node = self;
+        loop {
+            let (node_value, step) = mem::take(node).inner_append(index_path.pop_front(), value);
+            *node = node_value;
+            match step {
+                AppendStep::Done(Some(error)) => return Err(error),
+                AppendStep::Done(None) => return Ok(()),
+                AppendStep::Descend(indexer, child_value) => {
+                    node = node.child_mut(indexer);
+                    value = child_value;
+                }
+            }
         }
     }
 
@@ -220,39 +351,30 @@ impl<'a> Value<'a> {
     /// For a [`SparseList`](Value::SparseList), indices within `0..=max_index`
     /// that have no stored value return `Some(&Value::Empty)`; indices beyond
     /// `max_index` return `None`.
-    pub fn get<'b>(&self, key: impl TryInto<IndexPath<'b>>) -> Option<&Value<'a>> {
-        let mut index_path = key.try_into().ok()?;
-        let key = index_path.pop_front();
-        match (self, key) {
-            (Value::Map(m), Some(Indexer::String(key))) => {
-                m.get(&*key).and_then(|v| v.get(index_path))
-            }
-
-            (Value::List(l), Some(Indexer::Number(key))) => {
-                l.get(key).and_then(|v| v.get(index_path))
-            }
-
-            (Value::SparseList(m), Some(Indexer::Number(key))) => {
-                // Absent slots within 0..=max_key behave like Value::Empty (consistent
-                // with dense List), so q["a"][0] doesn't panic for a[2]=v.
-                // Uses a static to return a reference without any allocation.
-                static EMPTY: Value<'static> = Value::Empty;
-                let within_range = m.last_key_value().is_some_and(|(&max, _)| key <= max);
-                let v: &Value<'a> = match m.get(&key) {
+    pub fn get<'b>(&self, key: impl TryInto<IndexPath<'b>>) -> Option<&Value<'a, S>> {
+        // Absent slots within 0..=max_key of a SparseList behave like Value::Empty
+        // (consistent with dense List), so q["a"][0] doesn't panic for a[2]=v.
+        // The constant is promoted to a static, so this allocates nothing.
+        let empty: &Value<'a, S> = &Value::Empty;
+
+        let index_path = key.try_into().ok()?;
+        let mut node = self;
+        for key in index_path.iter() {
+            node = match (node, key) {
+                (Value::Map(m), Indexer::String(key)) => m.get(&**key)?,
+                (Value::List(l), Indexer::Number(key)) => l.get(*key)?,
+                (Value::SparseList(m), Indexer::Number(key)) => match m.get(key) {
                     Some(v) => v,
-                    None if within_range => &EMPTY,
+                    None if m.last_key_value().is_some_and(|(max, _)| key <= max) => empty,
                     None => return None,
-                };
-                v.get(index_path)
-            }
-
-            (this, None) => Some(this),
-
-            _ => None,
+                },
+                _ => return None,
+            };
         }
+        Some(node)
     }
 
-    /// Convenience wrapper around [`get`](Value::get) that extracts a `&str`.
+    /// Convenience wrapper around [`get`](Value::get) that extracts a string.
     ///
     /// Equivalent to `self.get(key).and_then(Value::as_str)`.
     pub fn get_str<'b>(&self, key: impl TryInto<IndexPath<'b>>) -> Option<&str> {
@@ -264,7 +386,7 @@ impl<'a> Value<'a> {
     /// Only succeeds for dense [`List`](Value::List) values; returns `None` for
     /// [`SparseList`](Value::SparseList).  Equivalent to
     /// `self.get(key).and_then(Value::as_slice)`.
-    pub fn get_slice<'b>(&self, key: impl TryInto<IndexPath<'b>>) -> Option<&[Value<'a>]> {
+    pub fn get_slice<'b>(&self, key: impl TryInto<IndexPath<'b>>) -> Option<&[Value<'a, S>]> {
         self.get(key).and_then(Value::as_slice)
     }
 
@@ -274,7 +396,7 @@ impl<'a> Value<'a> {
     pub fn get_map<'b>(
         &self,
         key: impl TryInto<IndexPath<'b>>,
-    ) -> Option<&BTreeMap<Cow<'a, str>, Value<'a>>> {
+    ) -> Option<&BTreeMap<S, Value<'a, S>>> {
         self.get(key).and_then(Value::as_map)
     }
 
@@ -284,7 +406,7 @@ impl<'a> Value<'a> {
     pub fn get_sparse_list<'b>(
         &self,
         key: impl TryInto<IndexPath<'b>>,
-    ) -> Option<&BTreeMap<usize, Value<'a>>> {
+    ) -> Option<&BTreeMap<usize, Value<'a, S>>> {
         self.get(key).and_then(Value::as_sparse_list)
     }
 
@@ -294,146 +416,211 @@ impl<'a> Value<'a> {
     ///
     /// Behaviour by container type:
     /// - **`Map`**: the key is removed from the map after the value is taken.
-    /// - **`List`**: converted to a `SparseList` with the slot removed, then
-    ///   potentially promoted to SparseList — so taking the last element stays dense, and taking
-    ///   a middle element leaves a `SparseList` (symmetric with how insert promotes
-    ///   `List` → `SparseList` when a gap is created).
+    /// - **`List`**: taking the last element pops it, so the list stays dense;
+    ///   taking a middle element leaves a gap, and the list becomes a
+    ///   `SparseList` (symmetric with how insert promotes `List` → `SparseList`
+    ///   when a gap is created).
     /// - **`SparseList`**: the entry is removed, then potentially promoted to a dense
     ///   [`List`][Value::List] if the remaining content is contiguous.
-    pub fn take<'b>(&mut self, key: impl TryInto<IndexPath<'b>>) -> Option<Value<'a>> {
-        let mut index_path = key.try_into().ok()?;
-        let current = index_path.pop_front();
-        let (new_self, result) = mem::take(self).inner_take(current, index_path);
-        *self = new_self;
-        result
-    }
-
-    // Like inner_append, takes self by value and returns (new_self, result) so that
-    // the caller can put the (possibly modified) value back.  This avoids borrow-checker
-    // issues when we need to mem::take an entry and then either restore or remove it.
-    fn inner_take(
-        self,
-        current_index: Option<Indexer<'_>>,
-        mut remaining: IndexPath<'_>,
-    ) -> (Self, Option<Value<'a>>) {
-        match (self, current_index) {
-            // Base case: take the whole node, leave Empty behind.
-            (value, None) => (Value::Empty, Some(value)),
-
-            // Map: recurse into the child entry.
-            // Remove the key only if something was actually taken and the entry is now Empty.
-            // If the deeper path didn't exist, new_entry is the original value — restore it.
-            (Value::Map(mut m), Some(Indexer::String(key))) => {
-                if !m.contains_key(&*key) {
-                    return (Value::Map(m), None);
-                }
-                let next = remaining.pop_front();
-                let entry_val = mem::take(m.get_mut(&*key).unwrap());
-                let (new_entry, result) = entry_val.inner_take(next, remaining);
-                if result.is_some() && new_entry.is_empty() {
-                    m.remove(&*key);
-                } else {
-                    *m.get_mut(&*key).unwrap() = new_entry;
-                }
-                (Value::Map(m), result)
-            }
+    pub fn take<'b>(&mut self, key: impl TryInto<IndexPath<'b>>) -> Option<Value<'a, S>> {
+        // Walks the path twice rather than recursing, so the stack stays flat
+        // however deep the path is.  The first walk finds the nodes along the
+        // path and works out, from the bottom up, which of them the removal
+        // leaves empty: such a node is removed from its parent in turn.  The
+        // second walk descends to the outermost of those, detaches it, and
+        // takes the value out of the detached subtree.
+        let index_path: IndexPath<'b> = key.try_into().ok()?;
+        let path: Vec<&Indexer<'b>> = index_path.iter().collect();
+
+        let mut nodes = vec![&*self];
+        // Whether the path ends at an absent slot of a sparse list, which
+        // reads as `Empty` and is left as it is
+        let mut absent = false;
+        for key in &path {
+            let node = nodes[nodes.len() - 1];
+            let child = match (node, *key) {
+                (Value::Map(m), Indexer::String(key)) => m.get(&**key)?,
+                (Value::List(l), Indexer::Number(n)) => l.get(*n)?,
+                (Value::SparseList(m), Indexer::Number(n)) => match m.get(n) {
+                    Some(child) => child,
+                    None if m.last_key_value().is_some_and(|(max, _)| n <= max) => {
+                        absent = true;
+                        break;
+                    }
+                    None => return None,
+                },
+                _ => return None,
+            };
+            nodes.push(child);
+        }
 
-            // Dense list: promote to BTreeMap, remove the entry, then try_densify.
-            // Taking the last element collapses back to a shorter List.
-            // Taking a middle element leaves a SparseList (gap created, symmetric with insert).
-            (Value::List(l), Some(Indexer::Number(n))) => {
-                let mut m: BTreeMap<usize, Value<'a>> = l.into_iter().enumerate().collect();
-                if !m.contains_key(&n) {
-                    return (try_densify(m), None);
+        // `nodes[depth]` is removed from its parent if the removal below it
+        // leaves it empty.  Find the outermost such depth.
+        let last = nodes.len() - 1;
+        let mut detach = None;
+        let mut emptied = if absent { nodes[last].is_empty() } else { true };
+        for depth in (1..=last).rev() {
+            if !emptied {
+                break;
+            }
+            detach = Some(depth);
+            emptied = nodes[depth - 1].is_empty_without(path[depth - 1]);
+        }
+        drop(nodes);
+
+        let mut node = self;
+        for (depth, key) in path.iter().enumerate() {
+            if detach == Some(depth + 1) {
+                let mut subtree = node.detach(key);
+                let mut target = &mut subtree;
+                for key in &path[depth + 1..last] {
+                    target = target.child_at_mut(key);
                 }
-                let next = remaining.pop_front();
-                let entry_val = mem::take(m.get_mut(&n).unwrap());
-                let (new_entry, result) = entry_val.inner_take(next, remaining);
-                if result.is_some() && new_entry.is_empty() {
-                    m.remove(&n);
+                return Some(if absent {
+                    Value::Empty
                 } else {
-                    *m.get_mut(&n).unwrap() = new_entry;
-                }
-                (try_densify(m), result)
+                    mem::take(target)
+                });
             }
+            if absent && depth == last {
+                break;
+            }
+            if let Value::SparseList(m) = node {
+                *node = try_densify(mem::take(m));
+            }
+            node = node.child_at_mut(key);
+        }
+        if absent {
+            Some(Value::Empty)
+        } else {
+            // Only an empty path gets here: take the whole value
+            Some(mem::take(node))
+        }
+    }
 
-            // SparseList: remove the entry if taken, then try_densify.
-            (Value::SparseList(mut m), Some(Indexer::Number(n))) => {
-                let within_range = m.last_key_value().is_some_and(|(&max, _)| n <= max);
-                if !m.contains_key(&n) {
-                    return (
-                        Value::SparseList(m),
-                        if within_range {
-                            Some(Value::Empty)
-                        } else {
-                            None
-                        },
-                    );
-                }
-                let next = remaining.pop_front();
-                let entry_val = mem::take(m.get_mut(&n).unwrap());
-                let (new_entry, result) = entry_val.inner_take(next, remaining);
-                if result.is_some() && new_entry.is_empty() {
-                    m.remove(&n);
-                } else {
-                    *m.get_mut(&n).unwrap() = new_entry;
-                }
-                (try_densify(m), result)
+    // Whether this node would be empty once the child at `key` is removed from
+    // it.  The child is known to be present.
+    fn is_empty_without(&self, key: &Indexer<'_>) -> bool {
+        match (self, key) {
+            (Value::Map(m), _) => m.len() == 1,
+            (Value::List(l), Indexer::Number(n)) => {
+                l.iter().enumerate().all(|(i, v)| i == *n || v.is_empty())
+            }
+            (Value::SparseList(m), Indexer::Number(n)) => {
+                m.iter().all(|(i, v)| i == n || v.is_empty())
             }
+            _ => false,
+        }
+    }
 
-            (other, _) => (other, None),
+    // The child at `key`, which the first walk of `take` found.
+    fn child_at_mut(&mut self, key: &Indexer<'_>) -> &mut Value<'a, S> {
+        match (self, key) {
+            (Value::Map(m), Indexer::String(key)) => m.get_mut(&**key),
+            (Value::List(l), Indexer::Number(n)) => l.get_mut(*n),
+            (Value::SparseList(m), Indexer::Number(n)) => m.get_mut(n),
+            _ => None,
         }
+        .expect("take checked the path before changing anything")
+    }
+
+    // Remove the child at `key` from this container, keeping a dense list
+    // dense only when its last element is removed, and densifying a sparse
+    // list that becomes contiguous.
+    fn detach(&mut self, key: &Indexer<'_>) -> Value<'a, S> {
+        let child = match (&mut *self, key) {
+            (Value::Map(m), Indexer::String(key)) => m.remove(&**key),
+            (Value::List(l), Indexer::Number(n)) if *n + 1 == l.len() => l.pop(),
+            (Value::List(l), Indexer::Number(n)) => {
+                let mut m: BTreeMap<usize, Value<'a, S>> =
+                    mem::take(l).into_iter().enumerate().collect();
+                let child = m.remove(n);
+                *self = Value::SparseList(m);
+                child
+            }
+            (Value::SparseList(m), Indexer::Number(n)) => {
+                let child = m.remove(n);
+                *self = try_densify(mem::take(m));
+                child
+            }
+            _ => None,
+        };
+        child.expect("take checked the path before changing anything")
     }
 
-    fn inner_append<'b: 'a>(
+    // Applies one segment of the path at this node, returning the new node and
+    // either the outcome or the child to continue into.
+    fn inner_append(
         self,
-        current_index: Option<Indexer<'b>>,
-        index_path: IndexPath<'b>,
-        value: Value<'b>,
-    ) -> (Self, Option<Error<'a>>) {
+        current_index: Option<Indexer<'a>>,
+        value: Value<'a, S>,
+    ) -> (Self, AppendStep<'a, S>) {
         match (self, current_index, value) {
-            (Value::Map(mut m), Some(Indexer::String(key)), value) => {
-                let err = m.entry(key).or_default().append(index_path, value).err();
-                (Value::Map(m), err)
-            }
-
-            (Value::Empty, None, value) => (value, None),
-            (Value::Empty, Some(Indexer::Empty), value) => (Value::List(vec![value]), None),
+            (Value::Map(m), Some(Indexer::String(key)), value) => (
+                Value::Map(m),
+                AppendStep::Descend(Indexer::String(key), value),
+            ),
 
-            (Value::Empty, Some(Indexer::String(s)), Value::Empty) => (Value::String(s), None),
-            (Value::Empty, Some(Indexer::String(s)), value) => Value::Map(BTreeMap::new())
-                .inner_append(Some(Indexer::String(s)), index_path, value),
+            (Value::Empty, None, value) => (value, AppendStep::Done(None)),
+            (Value::Empty, Some(Indexer::Empty), value) => {
+                (Value::List(vec![value]), AppendStep::Done(None))
+            }
 
-            (Value::String(s), None, value) | (Value::String(s), Some(Indexer::Empty), value) => {
-                (Self::List(vec![Value::String(s), value]), None)
+            (Value::Empty, Some(Indexer::String(s)), Value::Empty) => {
+                (Value::String(S::from_cow(s)), AppendStep::Done(None))
+            }
+            (Value::Empty, Some(Indexer::String(s)), value) => {
+                Value::Map(BTreeMap::new()).inner_append(Some(Indexer::String(s)), value)
             }
 
-            (Value::String(s1), Some(Indexer::String(s2)), Value::Empty) => {
-                (Self::List(vec![Value::String(s1), Value::String(s2)]), None)
+            (leaf @ (Value::String(_) | Value::Encoded(_)), None | Some(Indexer::Empty), value) => {
+                (Self::List(vec![leaf, value]), AppendStep::Done(None))
             }
 
+            (
+                leaf @ (Value::String(_) | Value::Encoded(_)),
+                Some(Indexer::String(s2)),
+                Value::Empty,
+            ) => (
+                Self::List(vec![leaf, Value::String(S::from_cow(s2))]),
+                AppendStep::Done(None),
+            ),
+
             // Dense list: [] or bare append
             (Value::List(mut l), Some(Indexer::Empty), value) => {
                 l.push(value);
-                (Value::List(l), None)
+                (Value::List(l), AppendStep::Done(None))
             }
 
             (Value::List(mut l), None, value) => {
                 l.push(value);
-                (Value::List(l), None)
+                (Value::List(l), AppendStep::Done(None))
             }
 
-            // Dense list + explicit [n]: promote to SparseList, then insert.
-            // If the result is contiguous 0..n, collapse back to a dense List.
-            (Value::List(l), Some(Indexer::Number(n)), value) => {
-                let mut m: BTreeMap<usize, Value<'a>> = l.into_iter().enumerate().collect();
-                let err = m.entry(n).or_default().append(index_path, value).err();
-                (try_densify(m), err)
+            // Dense list + explicit [n] within bounds: update the element in place.
+            (Value::List(l), Some(Indexer::Number(n)), value) if n < l.len() => (
+                Value::List(l),
+                AppendStep::Descend(Indexer::Number(n), value),
+            ),
+
+            // Dense list + explicit [n] at the end: push, staying dense.
+            (Value::List(mut l), Some(Indexer::Number(n)), value) if n == l.len() => {
+                l.push(Value::Empty);
+                (
+                    Value::List(l),
+                    AppendStep::Descend(Indexer::Number(n), value),
+                )
+            }
+
+            // Dense list + explicit [n] past the end: the gap promotes to SparseList.
+            (Value::List(l), current_index @ Some(Indexer::Number(_)), value) => {
+                Value::SparseList(l.into_iter().enumerate().collect())
+                    .inner_append(current_index, value)
             }
 
             (Value::List(mut l), Some(Indexer::String(s)), Value::Empty) => {
-                l.push(Value::String(s));
-                (Value::List(l), None)
+                l.push(Value::String(S::from_cow(s)));
+                (Value::List(l), AppendStep::Done(None))
             }
 
             (Value::List(l), Some(Indexer::String(s)), value) => {
@@ -445,15 +632,18 @@ impl<'a> Value<'a> {
                         Value::String(s) => {
                             map.insert(s, Value::Empty);
                         }
+                        Value::Encoded(e) => {
+                            map.insert(S::from_cow(e.into_cow()), Value::Empty);
+                        }
                         other if error.is_none() => {
-                            error = Some(Error::CouldNotConvertToMap(other));
+                            error = Some(Error::CouldNotConvertToMap(other.map_storage()));
                         }
                         _ => { /*subsequent errors currently ignored*/ }
                     }
                 }
 
-                map.insert(s, value);
-                (Value::Map(map), error)
+                map.insert(S::from_cow(s), value);
+                (Value::Map(map), AppendStep::Done(error))
             }
 
             // SparseList: [] or bare append (insert at last_key + 1)
@@ -461,20 +651,23 @@ impl<'a> Value<'a> {
             | (Value::SparseList(mut m), None, value) => {
                 let next = m.keys().last().map(|k| k + 1).unwrap_or(0);
                 m.insert(next, value);
-                (Value::SparseList(m), None)
+                (Value::SparseList(m), AppendStep::Done(None))
             }
 
             // SparseList: direct insert/update at [n].
-            // If the result is contiguous 0..n, collapse to a dense List.
+            // If the slot makes the indices contiguous 0..n, collapse to a dense List.
             (Value::SparseList(mut m), Some(Indexer::Number(n)), value) => {
-                let err = m.entry(n).or_default().append(index_path, value).err();
-                (try_densify(m), err)
+                m.entry(n).or_default();
+                (
+                    try_densify(m),
+                    AppendStep::Descend(Indexer::Number(n), value),
+                )
             }
 
             (Value::SparseList(mut m), Some(Indexer::String(s)), Value::Empty) => {
                 let next = m.keys().last().map(|k| k + 1).unwrap_or(0);
-                m.insert(next, Value::String(s));
-                (Value::SparseList(m), None)
+                m.insert(next, Value::String(S::from_cow(s)));
+                (Value::SparseList(m), AppendStep::Done(None))
             }
 
             (Value::SparseList(m), Some(Indexer::String(s)), value) => {
@@ -486,30 +679,56 @@ impl<'a> Value<'a> {
                         Value::String(s) => {
                             map.insert(s, Value::Empty);
                         }
+                        Value::Encoded(e) => {
+                            map.insert(S::from_cow(e.into_cow()), Value::Empty);
+                        }
                         other if error.is_none() => {
-                            error = Some(Error::CouldNotConvertToMap(other));
+                            error = Some(Error::CouldNotConvertToMap(other.map_storage()));
                         }
                         _ => {}
                     }
                 }
 
-                map.insert(s, value);
-                (Value::Map(map), error)
+                map.insert(S::from_cow(s), value);
+                (Value::Map(map), AppendStep::Done(error))
             }
 
-            (current_value, _, Value::Empty) => (current_value, None),
+            (current_value, _, Value::Empty) => (current_value, AppendStep::Done(None)),
 
             // Empty + [n]: start a SparseList directly (DoS-safe)
             (Value::Empty, current_index @ Some(Indexer::Number(_)), value) => {
-                Value::SparseList(BTreeMap::new()).inner_append(current_index, index_path, value)
+                Value::SparseList(BTreeMap::new()).inner_append(current_index, value)
             }
 
             (previous_value, indexer, new_value) => (
                 previous_value.clone(),
-                Some(Error::CouldNotAppend(previous_value, indexer, new_value)),
+                AppendStep::Done(Some(Error::CouldNotAppend(
+                    previous_value.map_storage(),
+                    indexer,
+                    new_value.map_storage(),
+                ))),
             ),
         }
     }
+
+    // The child that `inner_append` asked to descend into.  The step that
+    // returned `AppendStep::Descend` has already made sure it exists.
+    fn child_mut(&mut self, indexer: Indexer<'a>) -> &mut Value<'a, S> {
+        match (self, indexer) {
+            (Value::Map(m), Indexer::String(key)) => m.entry(S::from_cow(key)).or_default(),
+            (Value::List(l), Indexer::Number(n)) => &mut l[n],
+            (Value::SparseList(m), Indexer::Number(n)) => m.entry(n).or_default(),
+            _ => unreachable!("inner_append only descends into a matching container"),
+        }
+    }
+}
+
+// What `inner_append` did at one level of the path.
+enum AppendStep<'a, S> {
+    // The value was placed, or could not be.
+    Done(Option<Error<'a>>),
+    // Continue with the rest of the path at this child.
+    Descend(Indexer<'a>, Value<'a, S>),
 }
 
 /// If the BTreeMap's keys are exactly `0..n` (contiguous from zero), convert
@@ -517,7 +736,7 @@ impl<'a> Value<'a> {
 ///
 /// The check is O(log n): BTreeMap keys are sorted, so comparing the last key
 /// to `len - 1` is sufficient to determine contiguity.
-fn try_densify(m: BTreeMap<usize, Value<'_>>) -> Value<'_> {
+pub(crate) fn try_densify<S>(m: BTreeMap<usize, Value<'_, S>>) -> Value<'_, S> {
     match m.last_key_value() {
         Some((&last, _)) if last == m.len() - 1 => Value::List(m.into_values().collect()),
         _ => Value::SparseList(m),
@@ -580,40 +799,31 @@ impl From<()> for Value<'static> {
     }
 }
 
-impl PartialEq<()> for Value<'_> {
+impl<'a, S: StringStorage<'a>> PartialEq<()> for Value<'a, S> {
     fn eq(&self, _: &()) -> bool {
         self == &Self::Empty
     }
 }
 
-impl PartialEq<String> for Value<'_> {
+impl<'a, S: StringStorage<'a>> PartialEq<String> for Value<'a, S> {
     fn eq(&self, other: &String) -> bool {
-        match self {
-            Value::String(s) => s == other,
-            _ => false,
-        }
+        self.as_str().is_some_and(|s| s == *other)
     }
 }
 
-impl PartialEq<str> for Value<'_> {
+impl<'a, S: StringStorage<'a>> PartialEq<str> for Value<'a, S> {
     fn eq(&self, other: &str) -> bool {
-        match self {
-            Value::String(s) => s == other,
-            _ => false,
-        }
+        self.as_str().is_some_and(|s| s == other)
     }
 }
 
-impl PartialEq<&str> for Value<'_> {
+impl<'a, S: StringStorage<'a>> PartialEq<&str> for Value<'a, S> {
     fn eq(&self, other: &&str) -> bool {
-        match self {
-            Value::String(s) => s == other,
-            _ => false,
-        }
+        self.as_str().is_some_and(|s| s == *other)
     }
 }
 
-impl<'a: 'b, 'b> IntoIterator for &'a Value<'b> {
+impl<'a: 'b, 'b, S: StringStorage<'b>> IntoIterator for &'a Value<'b, S> {
     type Item = (Option<IndexPath<'b>>, Option<String>);
 
     type IntoIter = Box<dyn Iterator<Item = Self::Item> + 'a>;
@@ -655,12 +865,36 @@ impl<'a: 'b, 'b> IntoIterator for &'a Value<'b> {
 
             Value::String(s) => Box::new(iter::once((None, Some(crate::encode(s).into_owned())))),
 
+            Value::Encoded(e) => Box::new(iter::once((
+                None,
+                Some(crate::encode(e.as_str()).into_owned()),
+            ))),
+
             Value::Empty => Box::new(iter::once((None, None))),
         }
     }
 }
 
-impl<'a, Key: TryInto<IndexPath<'a>>> Index<Key> for Value<'a> {
+// An `Encoded` string equals the `String` it decodes to.
+impl<'a, S: StringStorage<'a>> PartialEq for Value<'a, S> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Map(a), Value::Map(b)) => a == b,
+            (Value::List(a), Value::List(b)) => a == b,
+            (Value::SparseList(a), Value::SparseList(b)) => a == b,
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::Empty, Value::Empty) => true,
+            (a, b) => match (a.as_str(), b.as_str()) {
+                (Some(a), Some(b)) => a == b,
+                _ => false,
+            },
+        }
+    }
+}
+
+impl<'a, S: StringStorage<'a>> Eq for Value<'a, S> {}
+
+impl<'a, S: StringStorage<'a>, Key: TryInto<IndexPath<'a>>> Index<Key> for Value<'a, S> {
     type Output = Self;
 
     fn index(&self, key: Key) -> &Self::Output {
@@ -669,17 +903,18 @@ impl<'a, Key: TryInto<IndexPath<'a>>> Index<Key> for Value<'a> {
 }
 
 #[cfg(feature = "serde")]
-impl serde::Serialize for Value<'_> {
-    fn serialize<S: serde::Serializer>(
+impl<'a, S: StringStorage<'a>> serde::Serialize for Value<'a, S> {
+    fn serialize<Ser: serde::Serializer>(
         &self,
-        serializer: S,
-    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer: Ser,
+    ) -> core::result::Result<Ser::Ok, Ser::Error> {
         match self {
-            Value::Map(m) => m.serialize(serializer),
+            Value::Map(m) => serializer.collect_map(m.iter().map(|(k, v)| (&**k, v))),
             Value::List(l) => l.serialize(serializer),
             // Serializes as an object with numeric string keys e.g. {"0": "x", "2": "y"}
             Value::SparseList(m) => m.serialize(serializer),
-            Value::String(s) => s.serialize(serializer),
+            Value::String(s) => (**s).serialize(serializer),
+            Value::Encoded(e) => e.as_str().serialize(serializer),
             Value::Empty => serializer.serialize_unit(),
         }
     }
@@ -0,0 +1,410 @@
+use crate::{IndexPath, Indexer, Value, value::try_densify};
+use alloc::{borrow::Cow, collections::BTreeMap, vec::Vec};
+use core::mem;
+
+/// Read-only traversal of a [`Value`] tree.
+///
+/// Every method has a default implementation that recurses into the node's
+/// children through the matching `walk_*` function, so an implementation only
+/// needs to override the variants it cares about.  An override that still
+/// wants to descend should call the corresponding `walk_*` function itself.
+///
+/// `path` is the location of the node being visited, relative to the value
+/// [`Value::visit`] was called on.  The walk functions push and pop segments
+/// on it as they descend, so implementations must leave it as they found it.
+///
+/// ```
+/// use querystrong::{IndexPath, QueryStrong, Visit};
+///
+/// #[derive(Default)]
+/// struct Leaves(Vec<String>);
+///
+/// impl<'a> Visit<'a> for Leaves {
+///     fn visit_string(&mut self, path: &mut IndexPath<'a>, _: &str) {
+///         self.0.push(path.to_string());
+///     }
+/// }
+///
+/// let qs = QueryStrong::parse("a[b]=1&a[c][]=2&d");
+/// let mut leaves = Leaves::default();
+/// qs.visit(&mut leaves);
+/// assert_eq!(leaves.0, ["a[b]", "a[c][0]"]);
+/// ```
+pub trait Visit<'a> {
+    /// Called for every node.  The default dispatches to the variant-specific method.
+    fn visit_value(&mut self, path: &mut IndexPath<'a>, value: &Value<'a>) {
+        walk_value(self, path, value);
+    }
+
+    /// Called for every [`Value::Map`].
+    fn visit_map(&mut self, path: &mut IndexPath<'a>, map: &BTreeMap<Cow<'a, str>, Value<'a>>) {
+        walk_map(self, path, map);
+    }
+
+    /// Called for every dense [`Value::List`].
+    fn visit_list(&mut self, path: &mut IndexPath<'a>, list: &[Value<'a>]) {
+        walk_list(self, path, list);
+    }
+
+    /// Called for every [`Value::SparseList`].  Absent slots are not visited.
+    fn visit_sparse_list(&mut self, path: &mut IndexPath<'a>, list: &BTreeMap<usize, Value<'a>>) {
+        walk_sparse_list(self, path, list);
+    }
+
+    /// Called for every [`Value::String`] leaf, and with the decoded string
+    /// for every [`Value::Encoded`] leaf.
+    fn visit_string(&mut self, _path: &mut IndexPath<'a>, _string: &str) {}
+
+    /// Called for every [`Value::Empty`] leaf.
+    fn visit_empty(&mut self, _path: &mut IndexPath<'a>) {}
+}
+
+/// Dispatch `value` to the variant-specific method of `visitor`.
+pub fn walk_value<'a, V: Visit<'a> + ?Sized>(
+    visitor: &mut V,
+    path: &mut IndexPath<'a>,
+    value: &Value<'a>,
+) {
+    match value {
+        Value::Map(m) => visitor.visit_map(path, m),
+        Value::List(l) => visitor.visit_list(path, l),
+        Value::SparseList(m) => visitor.visit_sparse_list(path, m),
+        Value::String(s) => visitor.visit_string(path, s),
+        Value::Encoded(e) => visitor.visit_string(path, e.as_str()),
+        Value::Empty => visitor.visit_empty(path),
+    }
+}
+
+/// Visit each entry of `map`, with its key pushed onto `path`.
+pub fn walk_map<'a, V: Visit<'a> + ?Sized>(
+    visitor: &mut V,
+    path: &mut IndexPath<'a>,
+    map: &BTreeMap<Cow<'a, str>, Value<'a>>,
+) {
+    for (key, value) in map {
+        path.push_back(Indexer::String(key.clone()));
+        visitor.visit_value(path, value);
+        path.pop_back();
+    }
+}
+
+/// Visit each element of `list`, with its position pushed onto `path`.
+pub fn walk_list<'a, V: Visit<'a> + ?Sized>(
+    visitor: &mut V,
+    path: &mut IndexPath<'a>,
+    list: &[Value<'a>],
+) {
+    for (n, value) in list.iter().enumerate() {
+        path.push_back(Indexer::Number(n));
+        visitor.visit_value(path, value);
+        path.pop_back();
+    }
+}
+
+/// Visit each populated slot of `list`, with its index pushed onto `path`.
+pub fn walk_sparse_list<'a, V: Visit<'a> + ?Sized>(
+    visitor: &mut V,
+    path: &mut IndexPath<'a>,
+    list: &BTreeMap<usize, Value<'a>>,
+) {
+    for (&n, value) in list {
+        path.push_back(Indexer::Number(n));
+        visitor.visit_value(path, value);
+        path.pop_back();
+    }
+}
+
+/// Mutable traversal of a [`Value`] tree.
+///
+/// The mutable counterpart of [`Visit`], with the same conventions for
+/// default methods and `path`.  Override
+/// [`visit_value_mut`](VisitMut::visit_value_mut) to replace a node with a
+/// different variant; map keys can be rewritten by rebuilding the map in
+/// [`visit_map_mut`](VisitMut::visit_map_mut).
+pub trait VisitMut<'a> {
+    /// Called for every node.  The default dispatches to the variant-specific method.
+    fn visit_value_mut(&mut self, path: &mut IndexPath<'a>, value: &mut Value<'a>) {
+        walk_value_mut(self, path, value);
+    }
+
+    /// Called for every [`Value::Map`].
+    fn visit_map_mut(
+        &mut self,
+        path: &mut IndexPath<'a>,
+        map: &mut BTreeMap<Cow<'a, str>, Value<'a>>,
+    ) {
+        walk_map_mut(self, path, map);
+    }
+
+    /// Called for every dense [`Value::List`].
+    fn visit_list_mut(&mut self, path: &mut IndexPath<'a>, list: &mut Vec<Value<'a>>) {
+        walk_list_mut(self, path, list);
+    }
+
+    /// Called for every [`Value::SparseList`].  Absent slots are not visited.
+    fn visit_sparse_list_mut(
+        &mut self,
+        path: &mut IndexPath<'a>,
+        list: &mut BTreeMap<usize, Value<'a>>,
+    ) {
+        walk_sparse_list_mut(self, path, list);
+    }
+
+    /// Called for every [`Value::String`] leaf.  A [`Value::Encoded`] leaf is
+    /// decoded into a `String` first.
+    fn visit_string_mut(&mut self, _path: &mut IndexPath<'a>, _string: &mut Cow<'a, str>) {}
+
+    /// Called for every [`Value::Empty`] leaf.
+    fn visit_empty_mut(&mut self, _path: &mut IndexPath<'a>) {}
+}
+
+/// Dispatch `value` to the variant-specific method of `visitor`.
+pub fn walk_value_mut<'a, V: VisitMut<'a> + ?Sized>(
+    visitor: &mut V,
+    path: &mut IndexPath<'a>,
+    value: &mut Value<'a>,
+) {
+    match value {
+        Value::Map(m) => visitor.visit_map_mut(path, m),
+        Value::List(l) => visitor.visit_list_mut(path, l),
+        Value::SparseList(m) => visitor.visit_sparse_list_mut(path, m),
+        Value::String(s) => visitor.visit_string_mut(path, s),
+        Value::Encoded(_) => {
+            if let Value::Encoded(e) = mem::take(value) {
+                *value = Value::String(e.into_cow());
+            }
+            walk_value_mut(visitor, path, value);
+        }
+        Value::Empty => visitor.visit_empty_mut(path),
+    }
+}
+
+/// Visit each entry of `map`, with its key pushed onto `path`.
+pub fn walk_map_mut<'a, V: VisitMut<'a> + ?Sized>(
+    visitor: &mut V,
+    path: &mut IndexPath<'a>,
+    map: &mut BTreeMap<Cow<'a, str>, Value<'a>>,
+) {
+    for (key, value) in map {
+        path.push_back(Indexer::String(key.clone()));
+        visitor.visit_value_mut(path, value);
+        path.pop_back();
+    }
+}
+
+/// Visit each element of `list`, with its position pushed onto `path`.
+pub fn walk_list_mut<'a, V: VisitMut<'a> + ?Sized>(
+    visitor: &mut V,
+    path: &mut IndexPath<'a>,
+    list: &mut [Value<'a>],
+) {
+    for (n, value) in list.iter_mut().enumerate() {
+        path.push_back(Indexer::Number(n));
+        visitor.visit_value_mut(path, value);
+        path.pop_back();
+    }
+}
+
+/// Visit each populated slot of `list`, with its index pushed onto `path`.
+pub fn walk_sparse_list_mut<'a, V: VisitMut<'a> + ?Sized>(
+    visitor: &mut V,
+    path: &mut IndexPath<'a>,
+    list: &mut BTreeMap<usize, Value<'a>>,
+) {
+    for (&n, value) in list {
+        path.push_back(Indexer::Number(n));
+        visitor.visit_value_mut(path, value);
+        path.pop_back();
+    }
+}
+
+impl<'a> Value<'a> {
+    /// Walk this value with a [`Visit`] implementation.
+    pub fn visit<V: Visit<'a> + ?Sized>(&self, visitor: &mut V) {
+        visitor.visit_value(&mut IndexPath::default(), self);
+    }
+
+    /// Walk this value with a [`VisitMut`] implementation.
+    pub fn visit_mut<V: VisitMut<'a> + ?Sized>(&mut self, visitor: &mut V) {
+        visitor.visit_value_mut(&mut IndexPath::default(), self);
+    }
+
+    /// Replace every [`String`](Value::String) leaf with the result of `f`.
+    ///
+    /// Map keys are left untouched; see [`map_keys`](Value::map_keys).
+    ///
+    /// ```
+    /// use querystrong::QueryStrong;
+    /// let mut qs = QueryStrong::parse("name=+jacob+&tags[]=+rust");
+    /// qs.map_strings(|s| s.trim().to_owned());
+    /// assert_eq!(qs.to_string(), "name=jacob&tags[]=rust");
+    /// ```
+    pub fn map_strings<F, S>(&mut self, f: F)
+    where
+        F: FnMut(Cow<'a, str>) -> S,
+        S: Into<Cow<'a, str>>,
+    {
+        struct MapStrings<F>(F);
+        impl<'a, F, S> VisitMut<'a> for MapStrings<F>
+        where
+            F: FnMut(Cow<'a, str>) -> S,
+            S: Into<Cow<'a, str>>,
+        {
+            fn visit_string_mut(&mut self, _: &mut IndexPath<'a>, string: &mut Cow<'a, str>) {
+                *string = (self.0)(mem::take(string)).into();
+            }
+        }
+
+        self.visit_mut(&mut MapStrings(f));
+    }
+
+    /// Replace every [`Map`](Value::Map) key with the result of `f`.
+    ///
+    /// If two keys of the same map are mapped to the same new key, the entry
+    /// that sorted last under the old keys wins.
+    ///
+    /// ```
+    /// use querystrong::QueryStrong;
+    /// let mut qs = QueryStrong::parse("User[Name]=jacob");
+    /// qs.map_keys(|k| k.to_lowercase());
+    /// assert_eq!(qs.to_string(), "user[name]=jacob");
+    /// ```
+    pub fn map_keys<F, S>(&mut self, f: F)
+    where
+        F: FnMut(Cow<'a, str>) -> S,
+        S: Into<Cow<'a, str>>,
+    {
+        struct MapKeys<F>(F);
+        impl<'a, F, S> VisitMut<'a> for MapKeys<F>
+        where
+            F: FnMut(Cow<'a, str>) -> S,
+            S: Into<Cow<'a, str>>,
+        {
+            fn visit_map_mut(
+                &mut self,
+                path: &mut IndexPath<'a>,
+                map: &mut BTreeMap<Cow<'a, str>, Value<'a>>,
+            ) {
+                *map = mem::take(map)
+                    .into_iter()
+                    .map(|(k, v)| ((self.0)(k).into(), v))
+                    .collect();
+                walk_map_mut(self, path, map);
+            }
+        }
+
+        self.visit_mut(&mut MapKeys(f));
+    }
+
+    /// Remove every node for which `f` returns `false`, along with its subtree.
+    ///
+    /// `f` is called top-down with the path of each node below `self` and the
+    /// node itself; the children of a removed node are not visited.  A dense
+    /// [`List`](Value::List) closes up around removed elements, since `[]`
+    /// notation carries no indices.  A [`SparseList`](Value::SparseList) keeps
+    /// its explicit indices and collapses to a dense list once they are
+    /// contiguous from zero, as on insertion.  Containers left empty are kept;
+    /// use [`compact`](Value::compact) to prune them.
+    ///
+    /// ```
+    /// use querystrong::QueryStrong;
+    /// let mut qs = QueryStrong::parse("a=1&b=&c[d]=&c[e]=2");
+    /// qs.retain(|_, value| value.as_str() != Some(""));
+    /// assert_eq!(qs.to_string(), "a=1&c[e]=2");
+    /// ```
+    pub fn retain<F>(&mut self, f: F)
+    where
+        F: FnMut(&IndexPath<'a>, &Value<'a>) -> bool,
+    {
+        struct Retain<F>(F);
+        impl<'a, F> VisitMut<'a> for Retain<F>
+        where
+            F: FnMut(&IndexPath<'a>, &Value<'a>) -> bool,
+        {
+            fn visit_value_mut(&mut self, path: &mut IndexPath<'a>, value: &mut Value<'a>) {
+                match value {
+                    Value::Map(m) => m.retain(|k, v| {
+                        path.push_back(Indexer::String(k.clone()));
+                        let keep = (self.0)(path, v);
+                        path.pop_back();
+                        keep
+                    }),
+
+                    Value::List(l) => {
+                        let mut n = 0;
+                        l.retain(|v| {
+                            path.push_back(Indexer::Number(n));
+                            let keep = (self.0)(path, v);
+                            path.pop_back();
+                            n += 1;
+                            keep
+                        });
+                    }
+
+                    Value::SparseList(m) => {
+                        m.retain(|&n, v| {
+                            path.push_back(Indexer::Number(n));
+                            let keep = (self.0)(path, v);
+                            path.pop_back();
+                            keep
+                        });
+                        *value = try_densify(mem::take(m));
+                    }
+
+                    Value::String(_) | Value::Encoded(_) | Value::Empty => {}
+                }
+
+                walk_value_mut(self, path, value);
+            }
+        }
+
+        self.visit_mut(&mut Retain(f));
+    }
+
+    /// Remove [`Empty`](Value::Empty) leaves, and any container that is left
+    /// with no entries as a result, throughout the tree.
+    ///
+    /// Lists follow the same rules as [`retain`](Value::retain): dense lists
+    /// close up, and sparse lists become a dense [`List`](Value::List) once
+    /// their remaining indices are contiguous from zero.  Empty *strings*
+    /// (`k=`) are kept.  `self` itself is never removed, only emptied.
+    ///
+    /// ```
+    /// use querystrong::QueryStrong;
+    /// let mut qs = QueryStrong::parse("a&b=&c[0]=x&c[1]&d[e]=1");
+    /// qs.take("d[e]");
+    /// qs.compact();
+    /// assert_eq!(qs.to_string(), "b=&c[]=x");
+    /// ```
+    pub fn compact(&mut self) {
+        struct Compact;
+        impl<'a> VisitMut<'a> for Compact {
+            fn visit_value_mut(&mut self, path: &mut IndexPath<'a>, value: &mut Value<'a>) {
+                walk_value_mut(self, path, value);
+                match value {
+                    Value::Map(m) => m.retain(|_, v| !v.is_vacant()),
+                    Value::List(l) => l.retain(|v| !v.is_vacant()),
+                    Value::SparseList(m) => {
+                        m.retain(|_, v| !v.is_vacant());
+                        *value = try_densify(mem::take(m));
+                    }
+                    Value::String(_) | Value::Encoded(_) | Value::Empty => {}
+                }
+            }
+        }
+
+        self.visit_mut(&mut Compact);
+    }
+
+    // Empty leaves and containers without any entries; unlike is_empty, an
+    // empty string is not vacant.
+    fn is_vacant(&self) -> bool {
+        match self {
+            Value::Map(m) => m.is_empty(),
+            Value::List(l) => l.is_empty(),
+            Value::SparseList(m) => m.is_empty(),
+            Value::String(_) | Value::Encoded(_) => false,
+            Value::Empty => true,
+        }
+    }
+}